@@ -1,34 +1,93 @@
-use std::{path::PathBuf, sync::mpsc, thread};
+use std::{collections::HashSet, path::PathBuf, sync::mpsc, thread, time::Duration};
+
+use anyhow::Context as _;
 
 use crate::{
+    backup, companion,
+    config::{self, Config},
+    credentials, diagnostics, fonts, gacha,
     games::{self, Game},
+    history, http,
+    i18n::{self, t, tn},
+    jobs, logs, stardb, stats,
+    sync::Sync,
     themes::{self, Theme},
-    ui,
+    ui, updater,
 };
 
 pub enum State {
-    #[cfg(not(debug_assertions))]
-    OutOfDate(self_update::Status),
+    /// Shown while the app closes for a restart
+    Restarting(String),
+    UpdateAvailable(updater::Release),
+    ReleaseNotes(updater::Notes),
+    Updates(updater::Settings),
+    Settings(Box<Settings>),
     Menu,
     Login(String, String),
+    Unlock(String),
+    Security(String, String),
     Waiting(String),
     PullMenu,
     Game,
-    Achievements(Vec<u32>),
+    Achievements(Achievements),
+    PullUrls(Vec<games::PullUrl>),
     Pulls(String),
+    PullHistory(gacha::History),
+    Archive(Vec<String>),
+    Stats(stats::Stats),
+    History(Vec<history::Snapshot>, Option<usize>, Option<usize>),
+    Proxy(http::Proxy),
+    Logs(Logs),
+    ThemeEditor(ThemeEditor),
     Error(String),
 }
 
 pub enum Message {
     GoTo(State),
+    /// Shows the waiting screen for the job with the id, cancelling it goes back
+    Wait(String, u64),
     Game(Game),
     Theme(Theme),
-    #[cfg(not(debug_assertions))]
-    Updated(Option<self_update::Status>),
-    User(Option<User>),
+    ReloadThemes,
+    SaveTheme(String, themes::Colors),
+    SyncMode(SyncMode),
+    /// Result of an update check, `true` if the user asked for it
+    UpdateChecked(anyhow::Result<Option<updater::Release>>, bool),
+    UpdateSettings(updater::Settings),
+    SaveSettings(Box<Settings>),
+    ResetConfig,
+    RemindLater,
+    Installed(String),
+    RolledBack,
+    User(User),
+    LoggedIn(User),
+    /// Logged in again from the relogin prompt
+    Relogged(User),
+    /// The session of the account expired, optionally with the request it interrupted
+    Expired(String, Option<Sync>),
+    Relogin(Option<Relogin>),
+    Renew,
+    Account(String),
     Path(PathBuf),
-    Logout,
+    /// Result of looking for the game
+    GamePath(Game, anyhow::Result<PathBuf>),
+    ExportDir(PathBuf),
+    Proxy(http::Proxy),
+    Logout(String),
+    Unlock(String),
+    SkipUnlock,
+    ForgetCredentials,
+    Protect(Option<credentials::Key>),
+    /// Result of an achievement capture
+    Captured(Game, anyhow::Result<Achievements>),
+    PullUrls(Game, anyhow::Result<Vec<games::PullUrl>>),
+    Companion(companion::Request),
     Toast(egui_notify::Toast),
+    /// A sync finished, it may have saved a newer backup
+    Synced(String),
+    /// A mirror sync would remove this many achievements
+    ConfirmMirror(Sync, usize),
+    Job(jobs::Event),
 }
 
 pub struct App {
@@ -37,22 +96,167 @@ pub struct App {
     pub state: State,
     pub game: games::Game,
     pub toasts: egui_notify::Toasts,
+    pub agent: ureq::Agent,
+    pub stardb: stardb::Client,
     pub theme: themes::Theme,
-    pub user: Option<User>,
-    pub paths: Paths,
+    /// Loaded from the themes dir
+    pub themes: Vec<themes::Custom>,
+    /// The `ui_scale` that egui uses right now
+    ui_scale: f32,
+    /// The colors egui is styled with right now
+    colors: themes::Colors,
+    /// The job the waiting screen is shown for
+    waiting_job: Option<u64>,
+    /// The screen shown last frame
+    screen: std::mem::Discriminant<State>,
+    pub users: Vec<User>,
+    pub key: Option<credentials::Key>,
+    /// Encrypted accounts that are waiting for the passphrase
+    pub locked: Option<credentials::Encrypted>,
+    pub config: Config,
+    pub relogin: Option<Relogin>,
+    pub jobs: Vec<jobs::Job>,
+    /// Shown again when the job behind `State::Waiting` is cancelled
+    back: State,
+    /// Running while enabled in the settings
+    companion: Option<companion::Server>,
+    egui_ctx: egui::Context,
+}
+
+/// Draft of the settings screen
+#[derive(Clone)]
+pub struct Settings {
+    /// The config when the screen was opened. Only what was changed since is saved, so
+    /// changes made elsewhere in the meantime stay
+    pub base: Config,
+    pub config: Config,
+    pub interfaces: Vec<games::Interface>,
+    /// Reset was clicked and waits for a confirmation
+    pub confirm_reset: bool,
+}
+
+impl Settings {
+    pub fn new(config: Config) -> Self {
+        let interfaces = games::interfaces().unwrap_or_else(|e| {
+            tracing::error!("Couldn't list capture interfaces: {e}");
+            Vec::new()
+        });
+
+        Self {
+            base: config.clone(),
+            config,
+            interfaces,
+            confirm_reset: false,
+        }
+    }
+
+    /// Only this screen changes the draft, so anything else changing the config doesn't count
+    pub fn unsaved(&self) -> bool {
+        self.config != self.base
+    }
+
+    /// `config` with the fields that were changed on this screen
+    pub fn apply(&self, config: &Config) -> Config {
+        fn changed<T: Clone + PartialEq>(target: &mut T, base: &T, edited: &T) {
+            if base != edited {
+                *target = edited.clone();
+            }
+        }
+
+        let (base, edited) = (&self.base, &self.config);
+        let mut config = config.clone();
+
+        changed(&mut config.interfaces, &base.interfaces, &edited.interfaces);
+        changed(&mut config.timeout, &base.timeout, &edited.timeout);
+        changed(&mut config.stardb_url, &base.stardb_url, &edited.stardb_url);
+        changed(&mut config.language, &base.language, &edited.language);
+        changed(&mut config.ui_scale, &base.ui_scale, &edited.ui_scale);
+        changed(&mut config.log_level, &base.log_level, &edited.log_level);
+        changed(&mut config.export_dir, &base.export_dir, &edited.export_dir);
+        changed(&mut config.sync_mode, &base.sync_mode, &edited.sync_mode);
+        changed(&mut config.companion, &base.companion, &edited.companion);
+
+        for game in [Game::Hsr, Game::Gi, Game::Zzz] {
+            let (base, edited) = (base.game(game), edited.game(game));
+            let target = config.game_mut(game);
+
+            changed(&mut target.path, &base.path, &edited.path);
+            changed(&mut target.account, &base.account, &edited.account);
+        }
+
+        config
+    }
+}
+
+/// Draft of the theme editor, previewed while it's open
+#[derive(Clone, PartialEq)]
+pub struct ThemeEditor {
+    pub name: String,
+    pub colors: themes::Colors,
+}
+
+#[derive(Clone)]
+pub struct Logs {
+    pub lines: Vec<logs::Line>,
+    pub level: config::LogLevel,
+    pub search: String,
+}
+
+/// Inline prompt for an account whose session expired
+#[derive(Clone)]
+pub struct Relogin {
+    pub username: String,
+    pub password: String,
+    pub sync: Option<Sync>,
+}
+
+#[derive(Clone)]
+pub struct Achievements {
+    pub completed: Vec<u32>,
+    pub selected: HashSet<u32>,
+    pub metadata: Vec<stardb::Achievement>,
+    pub search: String,
+    pub show_completed: bool,
+    pub show_missing: bool,
+    /// Newest backup of the current account, looked up when the screen is entered
+    pub backup: Option<backup::Backup>,
+    /// A mirror sync waiting for a confirmation and how many achievements it removes
+    pub confirm_mirror: Option<(Sync, usize)>,
+}
+
+impl Achievements {
+    pub fn new(completed: Vec<u32>, metadata: Vec<stardb::Achievement>) -> Self {
+        let selected = completed.iter().copied().collect();
+
+        Self {
+            completed,
+            selected,
+            metadata,
+            search: String::new(),
+            show_completed: true,
+            show_missing: true,
+            backup: None,
+            confirm_mirror: None,
+        }
+    }
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+/// How often saved sessions are renewed while the app is open
+const RENEW_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct User {
     pub id: String,
     pub username: String,
 }
 
-#[derive(Default, serde::Serialize, serde::Deserialize)]
-pub struct Paths {
-    pub zzz: Option<PathBuf>,
-    pub hsr: Option<PathBuf>,
-    pub gi: Option<PathBuf>,
+#[derive(Default, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum SyncMode {
+    /// Only adds the selected achievements to the account
+    #[default]
+    Merge,
+    /// Makes the account match the selected achievements exactly
+    Mirror,
 }
 
 impl App {
@@ -87,186 +291,806 @@ impl App {
             .and_then(|s| eframe::get_value(s, "theme"))
             .unwrap_or_default();
 
-        let user: Option<User> = cc
+        let mut users: Vec<User> = cc
             .storage
-            .and_then(|s| eframe::get_value(s, "user").unwrap_or_default());
+            .and_then(|s| {
+                eframe::get_value(s, "users").or_else(|| {
+                    eframe::get_value::<Option<User>>(s, "user").map(|u| u.into_iter().collect())
+                })
+            })
+            .unwrap_or_default();
 
-        let paths: Paths = cc
+        let encrypted: Option<credentials::Encrypted> = cc
             .storage
-            .and_then(|s| eframe::get_value(s, "paths"))
-            .unwrap_or_default();
+            .and_then(|s| eframe::get_value(s, "credentials").unwrap_or_default());
 
-        cc.egui_ctx.set_style(theme.style());
+        let (key, locked) = match encrypted {
+            Some(encrypted) if encrypted.protection == credentials::Protection::Machine => {
+                match credentials::Key::unlock(&encrypted, "") {
+                    Ok((key, unlocked)) => {
+                        for user in unlocked {
+                            upsert_user(&mut users, user);
+                        }
 
-        let (message_tx, message_rx) = mpsc::channel();
+                        (Some(key), None)
+                    }
+                    Err(e) => {
+                        tracing::error!("Couldn't unlock accounts: {e}");
+                        (None, Some(encrypted))
+                    }
+                }
+            }
+            encrypted => (None, encrypted),
+        };
 
-        update(&message_tx);
-
-        if let Some(user) = &user {
-            let message_tx = message_tx.clone();
-            let id = user.id.clone();
-
-            thread::spawn(move || {
-                let Some(mut response) = ureq::post("https://stardb.gg/api/users/auth/renew")
-                    .header("Cookie", &id)
-                    .send_empty()
-                    .ok()
-                    .and_then(|r| (r.status() == 200).then_some(r))
-                else {
-                    message_tx
-                        .send(Message::GoTo(State::Error(
-                            "There was an error renewing your account cookie".to_string(),
-                        )))
-                        .unwrap();
-                    message_tx.send(Message::User(None)).unwrap();
-                    return;
-                };
+        let config = Config::load(cc.storage);
+
+        crate::set_log_level(config.log_level);
+        i18n::set_language(config.language);
+
+        cc.egui_ctx.set_zoom_factor(config.ui_scale);
+
+        let custom_themes = themes::load();
+        let colors = theme.colors(&custom_themes, cc.egui_ctx.system_theme());
+        set_style(&cc.egui_ctx, colors);
+
+        let agent = http::agent(&config.proxy, config.timeout()).unwrap_or_else(|e| {
+            tracing::error!("Invalid proxy, falling back to the environment: {e}");
+            http::agent(&http::Proxy::default(), config.timeout()).unwrap()
+        });
+
+        let stardb = stardb::Client::new(&config.stardb_url(), agent.clone());
 
-                let id = response
-                    .headers()
-                    .get("Set-Cookie")
-                    .unwrap()
-                    .to_str()
-                    .unwrap()
-                    .split(';')
-                    .next()
-                    .unwrap()
-                    .to_string();
-                let username = response.body_mut().read_json().unwrap();
-
-                let user = User { id, username };
-                message_tx.send(Message::User(Some(user))).unwrap();
-            });
+        let (message_tx, message_rx) = mpsc::channel();
+
+        for user in &users {
+            renew(&stardb, user, &message_tx);
         }
 
-        Self {
+        let renew_tx = message_tx.clone();
+        thread::spawn(move || {
+            loop {
+                thread::sleep(RENEW_INTERVAL);
+
+                if renew_tx.send(Message::Renew).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut app = Self {
             message_tx,
             message_rx,
-            state: State::Waiting("Updating".to_string()),
+            state: State::Unlock(String::new()),
             game: games::Game::Hsr,
             toasts: egui_notify::Toasts::default().with_anchor(egui_notify::Anchor::BottomRight),
+            agent,
+            stardb,
             theme,
-            user,
-            paths,
+            themes: custom_themes,
+            ui_scale: config.ui_scale,
+            colors,
+            waiting_job: None,
+            screen: std::mem::discriminant(&State::Menu),
+            users,
+            key,
+            locked,
+            config,
+            relogin: None,
+            jobs: Vec::new(),
+            back: State::Menu,
+            companion: None,
+            egui_ctx: cc.egui_ctx.clone(),
+        };
+
+        app.start_companion();
+
+        if app.locked.is_none() {
+            app.start();
+        }
+
+        app
+    }
+
+    /// The account the current game syncs to, falling back to the first saved account
+    pub fn user(&self) -> Option<&User> {
+        self.game_user(self.game)
+    }
+
+    /// The account `game` syncs to
+    pub fn game_user(&self, game: Game) -> Option<&User> {
+        self.config
+            .game(game)
+            .account
+            .as_ref()
+            .and_then(|a| {
+                self.users
+                    .iter()
+                    .find(|u| u.username.eq_ignore_ascii_case(a))
+            })
+            .or(self.users.first())
+    }
+
+    fn start(&mut self) {
+        if let Some(notes) = updater::take_notes() {
+            self.state = State::ReleaseNotes(notes);
+        } else if self.config.updates.check_on_startup() {
+            let id = self.check_updates(false);
+            self.state = State::Waiting(t!("update.checking"));
+            self.waiting_job = Some(id);
+            self.back = State::Menu;
+        } else {
+            self.state = State::Menu;
+        }
+    }
+
+    pub fn check_updates(&self, manual: bool) -> u64 {
+        let agent = self.agent.clone();
+        let updates = self.config.updates.clone();
+
+        jobs::spawn(
+            &self.message_tx,
+            t!("update.checking"),
+            move |_| updater::check(&agent, &updates, manual),
+            move |result| Message::UpdateChecked(result, manual),
+        )
+    }
+
+    /// Looks for the game first if there's no `path`
+    pub fn pull_urls(&self, game: Game, path: Option<PathBuf>) -> u64 {
+        let agent = self.agent.clone();
+
+        jobs::spawn(
+            &self.message_tx,
+            t!("pull_menu.validating"),
+            move |context| {
+                let path = match path {
+                    Some(path) => path,
+                    None => {
+                        context.progress(t!("pull_menu.finding"), None);
+
+                        game.game_path()
+                            .context(t!("companion.no_path", game = game.name()))?
+                    }
+                };
+
+                games::pull_urls(game, &path, &agent, context)
+            },
+            move |result| Message::PullUrls(game, result),
+        )
+    }
+
+    /// Scanning wine prefixes and logs takes a while, so it runs as a job
+    pub fn find_game_path(&self, game: Game) {
+        jobs::spawn(
+            &self.message_tx,
+            t!("pull_menu.finding"),
+            move |_| game.game_path(),
+            move |result| Message::GamePath(game, result),
+        );
+    }
+
+    /// Stops the companion server and starts it again if it's enabled
+    fn start_companion(&mut self) {
+        // The old server has to let go of the port first
+        self.companion = None;
+
+        if !self.config.companion.enabled {
+            return;
+        }
+
+        match companion::Server::start(&self.config.companion, &self.message_tx, &self.egui_ctx) {
+            Ok(server) => self.companion = Some(server),
+            Err(e) => {
+                tracing::error!("Couldn't start the companion server: {e}");
+                self.toasts
+                    .add(egui_notify::Toast::error(t!("companion.failed", error = e)));
+            }
+        }
+    }
+
+    fn broadcast(&self, event: companion::Event) {
+        if let Some(companion) = &self.companion {
+            companion.broadcast(event);
+        }
+    }
+
+    /// Something the stardb website asked for through the companion server
+    fn companion(&mut self, request: companion::Request) {
+        let (game, name) = match request {
+            companion::Request::Status(reply) => {
+                let _ = reply.send(companion::status(self));
+                return;
+            }
+            companion::Request::Achievements(game) => (game.unwrap_or(self.game), "achievements"),
+            companion::Request::PullUrls(game) => (game.unwrap_or(self.game), "pull_urls"),
+        };
+
+        // Starting a capture would throw away unsaved edits or race a running job
+        let busy = !self.jobs.is_empty()
+            || matches!(
+                self.state,
+                State::Unlock(_)
+                    | State::Waiting(_)
+                    | State::Restarting(_)
+                    | State::Login(..)
+                    | State::Security(..)
+                    | State::Settings(_)
+                    | State::Proxy(_)
+                    | State::Updates(_)
+                    | State::ThemeEditor(_)
+            );
+
+        let error = if busy {
+            Some(t!("companion.busy"))
+        } else if name == "achievements" && game == Game::Zzz {
+            Some(t!("companion.unsupported", game = game.name()))
+        } else {
+            None
+        };
+
+        if let Some(error) = error {
+            self.broadcast(companion::Event::Error(name, game, error));
+            return;
+        }
+
+        if name == "achievements" {
+            let id = game.achievements(&self.stardb, &self.config.interfaces, &self.message_tx);
+
+            self.wait(t!("game.preparing"), id);
+            self.toasts
+                .add(egui_notify::Toast::info(t!("companion.capture")));
+        } else {
+            let id = self.pull_urls(game, self.config.game(game).path.clone());
+
+            self.wait(t!("common.running"), id);
+            self.toasts
+                .add(egui_notify::Toast::info(t!("companion.pull_urls")));
+        }
+
+        self.game = game;
+        self.egui_ctx
+            .send_viewport_cmd(egui::ViewportCommand::Focus);
+    }
+
+    /// Text on screen that might need a CJK font
+    fn texts(&self) -> impl Iterator<Item = &str> {
+        let state: Box<dyn Iterator<Item = &str>> = match &self.state {
+            State::Settings(_) => Box::new(i18n::Language::ALL.into_iter().map(|l| l.name())),
+            State::Achievements(achievements) => Box::new(
+                achievements
+                    .metadata
+                    .iter()
+                    .flat_map(|a| [a.name.as_str(), a.series_name.as_str()]),
+            ),
+            State::PullHistory(history) => Box::new(history.pulls.iter().map(|p| p.name.as_str())),
+            State::Stats(stats) => Box::new(
+                stats
+                    .banners
+                    .iter()
+                    .flat_map(|b| b.five_stars.iter().map(|f| f.name.as_str())),
+            ),
+            _ => Box::new(std::iter::empty()),
+        };
+
+        let paths = [&self.config.hsr, &self.config.gi, &self.config.zzz]
+            .into_iter()
+            .map(|g| &g.path)
+            .chain([&self.config.export_dir])
+            .filter_map(|p| p.as_deref()?.to_str());
+
+        std::iter::once(i18n::language().name())
+            .chain(self.users.iter().map(|u| u.username.as_str()))
+            .chain(paths)
+            .chain(state)
+    }
+
+    /// Redacts the secrets this app knows about, for logs that get shared
+    pub fn redactor(&self) -> diagnostics::Redactor {
+        let mut secrets: Vec<String> = self.users.iter().map(|u| u.id.clone()).collect();
+        secrets.push(self.config.proxy.password.clone());
+        secrets.push(self.config.companion.token.clone());
+        secrets.extend(diagnostics::userinfo(&self.config.proxy.url).map(|u| u.to_string()));
+
+        diagnostics::Redactor::new(secrets)
+    }
+
+    /// Rebuilds everything that depends on the config
+    fn set_config(&mut self, config: Config) -> anyhow::Result<()> {
+        let agent = http::agent(&config.proxy, config.timeout())?;
+
+        self.stardb = stardb::Client::new(&config.stardb_url(), agent.clone());
+        self.agent = agent;
+
+        crate::set_log_level(config.log_level);
+        i18n::set_language(config.language);
+
+        let restart = config.companion != self.config.companion;
+        self.config = config;
+
+        if restart {
+            self.start_companion();
+        }
+
+        Ok(())
+    }
+
+    fn go_to(&mut self, state: State) {
+        let previous = std::mem::replace(&mut self.state, state);
+
+        if matches!(self.state, State::Achievements(_))
+            && !matches!(previous, State::Achievements(_))
+        {
+            self.load_backup();
+        }
+
+        if matches!(self.state, State::Waiting(_)) && !matches!(previous, State::Waiting(_)) {
+            self.back = previous;
+        }
+    }
+
+    /// The new process reads the config and binds the companion port before this one exits,
+    /// so both are taken care of first
+    fn restart(&mut self, reason: String) {
+        if let Err(e) = self.config.save() {
+            tracing::error!("Couldn't save the config before restarting: {e}");
+        }
+
+        self.companion = None;
+        self.state = State::Restarting(reason);
+
+        updater::restart();
+    }
+
+    fn wait(&mut self, text: String, job: u64) {
+        self.go_to(State::Waiting(text));
+        self.waiting_job = Some(job);
+    }
+
+    fn load_backup(&mut self) {
+        let backup = self
+            .user()
+            .and_then(|u| backup::latest(self.game, &u.username));
+
+        if let State::Achievements(achievements) = &mut self.state {
+            achievements.backup = backup;
         }
     }
 
     fn message(&mut self, message: Message) {
         match message {
-            Message::GoTo(state) => {
-                self.state = state;
-            }
+            Message::GoTo(state) => self.go_to(state),
+            Message::Wait(text, job) => self.wait(text, job),
             Message::Game(game) => {
                 self.game = game;
                 self.state = State::Game;
             }
-            #[cfg(not(debug_assertions))]
-            Message::Updated(status) => {
-                if let Some(status) = status {
-                    if status.updated() {
-                        self.state = State::OutOfDate(status);
-
-                        let program_name = std::env::args().next().unwrap();
-                        let _ = std::process::Command::new(program_name).spawn();
+            Message::UpdateChecked(result, manual) => match result {
+                Ok(Some(release)) => self.state = State::UpdateAvailable(release),
+                Ok(None) => {
+                    if manual {
+                        self.toasts
+                            .add(egui_notify::Toast::info(t!("update.latest")));
                     } else {
                         self.state = State::Menu;
                     }
-                } else {
-                    self.state = State::Error("Error updating".to_string());
                 }
+                Err(e) => {
+                    self.toasts.add(egui_notify::Toast::warning(t!(
+                        "update.check_failed",
+                        error = e
+                    )));
+
+                    if !manual {
+                        self.state = State::Menu;
+                    }
+                }
+            },
+            Message::UpdateSettings(updates) => {
+                self.config.updates = updates;
+
+                self.toasts
+                    .add(egui_notify::Toast::success(t!("common.saved")));
+            }
+            Message::RemindLater => {
+                self.config.updates.remind_after =
+                    Some(chrono::Utc::now() + chrono::Duration::days(1));
+                self.state = State::Menu;
+            }
+            Message::Installed(version) => {
+                self.restart(t!("update.updated", version = version));
+            }
+            Message::RolledBack => {
+                // Don't offer the version that was just rolled back from again
+                self.config.updates.skipped = Some(env!("CARGO_PKG_VERSION").to_string());
+                self.restart(t!("update.rolled_back"));
             }
             Message::Theme(theme) => self.theme = theme,
-            Message::User(user) => self.user = user,
-            Message::Path(path) => match self.game {
-                games::Game::Hsr => self.paths.hsr = Some(path),
-                games::Game::Gi => self.paths.gi = Some(path),
-                games::Game::Zzz => self.paths.zzz = Some(path),
+            Message::ReloadThemes => self.themes = themes::load(),
+            Message::SaveTheme(name, colors) => match themes::save(&name, &colors, &self.themes) {
+                Ok(()) => {
+                    self.themes = themes::load();
+                    self.theme = Theme::Custom(name);
+                    self.toasts
+                        .add(egui_notify::Toast::success(t!("common.saved")));
+                }
+                Err(e) => {
+                    self.toasts.add(egui_notify::Toast::error(e.to_string()));
+                }
+            },
+            Message::SyncMode(sync_mode) => self.config.sync_mode = sync_mode,
+            Message::User(user) => upsert_user(&mut self.users, user),
+            Message::LoggedIn(user) => {
+                let username = user.username.clone();
+                upsert_user(&mut self.users, user);
+
+                self.message(Message::Account(username));
+                self.state = State::Menu;
+            }
+            Message::Relogged(user) => {
+                let sync = self.relogin.take().and_then(|r| r.sync);
+
+                if let Some(sync) = sync {
+                    sync.spawn(&self.stardb, Some(&user), &self.message_tx);
+                }
+
+                upsert_user(&mut self.users, user);
+                self.toasts
+                    .add(egui_notify::Toast::success(t!("login.logged_in")));
+            }
+            Message::Expired(username, sync) => match &mut self.relogin {
+                Some(relogin) if relogin.username == username => {
+                    if sync.is_some() {
+                        relogin.sync = sync;
+                    }
+                }
+                _ => {
+                    self.relogin = Some(Relogin {
+                        username,
+                        password: String::new(),
+                        sync,
+                    })
+                }
+            },
+            Message::Relogin(relogin) => self.relogin = relogin,
+            Message::Renew => {
+                for user in &self.users {
+                    renew(&self.stardb, user, &self.message_tx);
+                }
+            }
+            Message::Account(username) => {
+                self.config.game_mut(self.game).account = Some(username);
+                self.load_backup();
+            }
+            Message::Path(path) => self.config.game_mut(self.game).path = Some(path),
+            Message::GamePath(game, result) => match result {
+                Ok(path) => self.config.game_mut(game).path = Some(path),
+                Err(e) => self.state = State::Error(e.to_string()),
             },
-            Message::Logout => {
-                let Some(user) = &self.user else {
+            Message::ExportDir(path) => self.config.export_dir = Some(path),
+            Message::Proxy(proxy) => {
+                let config = Config {
+                    proxy,
+                    ..self.config.clone()
+                };
+
+                match self.set_config(config) {
+                    Ok(()) => {
+                        self.toasts
+                            .add(egui_notify::Toast::success(t!("common.saved")));
+                    }
+                    Err(e) => {
+                        self.toasts
+                            .add(egui_notify::Toast::error(t!("proxy.invalid", error = e)));
+                    }
+                }
+            }
+            Message::SaveSettings(settings) => {
+                match self.set_config(settings.apply(&self.config)) {
+                    Ok(()) => {
+                        // What's saved now is where the next changes start from
+                        if let State::Settings(settings) = &mut self.state {
+                            settings.base = settings.config.clone();
+                        }
+
+                        self.toasts
+                            .add(egui_notify::Toast::success(t!("common.saved")));
+                    }
+                    Err(e) => {
+                        self.toasts
+                            .add(egui_notify::Toast::error(t!("settings.invalid", error = e)));
+                    }
+                }
+            }
+            Message::ResetConfig => {
+                // Also rebuilds the agent, so the proxy is gone right away
+                if let Err(e) = self.set_config(Config::default()) {
+                    tracing::error!("Couldn't apply the default settings: {e}");
+                }
+
+                self.state = State::Settings(Box::new(Settings::new(self.config.clone())));
+                self.toasts
+                    .add(egui_notify::Toast::info(t!("settings.reset_done")));
+            }
+            Message::Logout(username) => {
+                let Some(i) = self.users.iter().position(|u| u.username == username) else {
                     return;
                 };
 
-                let id = user.id.clone();
-                self.user = None;
+                let id = self.users.remove(i).id;
+                self.load_backup();
+                let stardb = self.stardb.clone();
 
-                thread::spawn(move || {
-                    let _ = ureq::post("https://stardb.gg/api/users/auth/logout")
-                        .header("Cookie", &id)
-                        .send_empty();
-                });
+                jobs::spawn(
+                    &self.message_tx,
+                    t!("logout.job", username = username),
+                    move |_| Ok(stardb.logout(&id)?),
+                    |result| match result {
+                        Ok(()) => Message::Toast(egui_notify::Toast::success(t!("logout.done"))),
+                        Err(e) => Message::Toast(egui_notify::Toast::warning(t!(
+                            "logout.failed",
+                            error = e
+                        ))),
+                    },
+                );
             }
+            Message::Unlock(passphrase) => {
+                let Some(locked) = &self.locked else {
+                    return;
+                };
+
+                match credentials::Key::unlock(locked, &passphrase) {
+                    Ok((key, users)) => {
+                        for user in users {
+                            renew(&self.stardb, &user, &self.message_tx);
+                            upsert_user(&mut self.users, user);
+                        }
+
+                        self.key = Some(key);
+                        self.locked = None;
+                        self.start();
+                    }
+                    Err(e) => {
+                        self.toasts.add(egui_notify::Toast::error(e.to_string()));
+                    }
+                }
+            }
+            Message::SkipUnlock => self.start(),
+            Message::ForgetCredentials => {
+                self.locked = None;
+                self.start();
+            }
+            Message::Protect(key) => self.key = key,
+            Message::Captured(game, result) => match result {
+                Ok(achievements) => {
+                    self.broadcast(companion::Event::Achievements(
+                        game,
+                        &achievements.completed,
+                    ));
+                    self.go_to(State::Achievements(achievements));
+                }
+                Err(e) => {
+                    self.broadcast(companion::Event::Error("achievements", game, e.to_string()));
+                    self.state = State::Error(e.to_string());
+                }
+            },
+            Message::PullUrls(game, result) => match result {
+                Ok(pull_urls) => {
+                    if let Err(e) = history::save_pull_urls(game, &pull_urls) {
+                        tracing::error!("Couldn't save history snapshot: {e}");
+                    }
+
+                    self.broadcast(companion::Event::PullUrls(game, &pull_urls));
+                    self.state = State::PullUrls(pull_urls);
+                }
+                Err(e) => {
+                    self.broadcast(companion::Event::Error("pull_urls", game, e.to_string()));
+                    self.state = State::Error(e.to_string());
+                }
+            },
+            Message::Companion(request) => self.companion(request),
             Message::Toast(toast) => {
                 self.toasts.add(toast);
             }
+            Message::Synced(message) => {
+                self.toasts.add(egui_notify::Toast::success(message));
+                self.load_backup();
+            }
+            Message::ConfirmMirror(sync, count) => match &mut self.state {
+                State::Achievements(achievements) => {
+                    achievements.confirm_mirror = Some((sync, count));
+                }
+                _ => {
+                    self.toasts.add(egui_notify::Toast::warning(tn!(
+                        "sync.mirror_confirm",
+                        count
+                    )));
+                }
+            },
+            Message::Job(jobs::Event::Started(job)) => self.jobs.push(job),
+            Message::Job(jobs::Event::Progress(id, progress, fraction)) => {
+                if let Some(job) = self.jobs.iter_mut().find(|j| j.id == id) {
+                    job.progress = progress;
+                    job.fraction = fraction;
+                }
+            }
+            Message::Job(jobs::Event::Finished { id, cancelled }) => {
+                self.jobs.retain(|j| j.id != id);
+
+                if cancelled {
+                    self.toasts
+                        .add(egui_notify::Toast::info(t!("jobs.cancelled")));
+                }
+
+                // Other jobs, like renewing sessions in the background, don't own the screen
+                if self.waiting_job == Some(id) {
+                    self.waiting_job = None;
+
+                    if cancelled && matches!(self.state, State::Waiting(_)) {
+                        self.state = std::mem::replace(&mut self.back, State::Menu);
+                    }
+                }
+            }
         }
     }
 }
 
 impl eframe::App for App {
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        eframe::set_value(storage, "user", &self.user);
+        match (&self.key, &self.locked) {
+            (Some(key), _) => match key.encrypt(&self.users) {
+                Ok(encrypted) => {
+                    eframe::set_value(storage, "credentials", &Some(encrypted));
+                    eframe::set_value(storage, "users", &Vec::<User>::new());
+                }
+                Err(e) => tracing::error!("Couldn't encrypt accounts: {e}"),
+            },
+            (None, locked) => {
+                eframe::set_value(storage, "credentials", locked);
+                eframe::set_value(storage, "users", &self.users);
+            }
+        }
+        // Superseded by "users" and "credentials", cleared so no plaintext cookie is left behind
+        eframe::set_value(storage, "user", &None::<User>);
         eframe::set_value(storage, "theme", &self.theme);
-        eframe::set_value(storage, "paths", &self.paths);
+
+        if let Err(e) = self.config.save() {
+            tracing::error!("Couldn't save the config: {e}");
+        }
     }
 
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        if let Ok(message) = self.message_rx.try_recv() {
+        while let Ok(message) = self.message_rx.try_recv() {
             self.message(message);
         }
 
-        ctx.set_style(self.theme.style());
+        let colors = match &self.state {
+            State::ThemeEditor(editor) => editor.colors,
+            _ => self.theme.colors(&self.themes, ctx.system_theme()),
+        };
+        // Rebuilding the style every frame is wasted work
+        if colors != self.colors {
+            set_style(ctx, colors);
+            self.colors = colors;
+        }
+
+        // A changed setting wins, otherwise ctrl + and ctrl - changed the zoom
+        if self.config.ui_scale != self.ui_scale {
+            ctx.set_zoom_factor(self.config.ui_scale);
+        } else if ctx.zoom_factor() != self.ui_scale {
+            self.config.ui_scale = ctx.zoom_factor();
+        }
+        self.ui_scale = self.config.ui_scale;
+
+        fonts::ensure(ctx, self.texts());
+
+        let screen = std::mem::discriminant(&self.state);
+        if screen != self.screen {
+            self.screen = screen;
+            ui::focus::entered(ctx);
+        }
+
+        if !self.jobs.is_empty() {
+            egui::TopBottomPanel::bottom("jobs").show(ctx, |ui| ui::jobs::show(ui, &self.jobs));
+
+            // Progress arrives from other threads
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
 
         egui::CentralPanel::default().show(ctx, |ui| {
             ui::header::show(ctx, ui, self);
 
             ui.separator();
 
+            if let Some(relogin) = &self.relogin {
+                ui::relogin::show(ui, relogin, self);
+
+                ui.separator();
+            }
+
             match &self.state {
                 State::Waiting(s) => ui::waiting::show(ui, s),
-                #[cfg(not(debug_assertions))]
-                State::OutOfDate(status) => {
-                    ui::waiting::show(
-                        ui,
-                        &format!("Updated to Version {}. Restarting!", status.version()),
-                    );
+                State::Restarting(s) => {
+                    ui::waiting::show(ui, &t!("update.restarting", reason = s));
 
                     ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                 }
+                State::UpdateAvailable(release) => ui::update::available(ui, release, self),
+                State::ReleaseNotes(notes) => ui::update::notes(ui, notes, self),
+                State::Updates(updates) => ui::update::settings(ui, updates, self),
+                State::Settings(settings) => ui::settings::show(ui, settings, self),
                 State::Login(username, password) => ui::login::show(ui, username, password, self),
+                State::Unlock(passphrase) => ui::unlock::show(ui, passphrase, self),
+                State::Security(passphrase, confirm) => {
+                    ui::security::show(ui, passphrase, confirm, self)
+                }
                 State::Menu => ui::menu::show(ui, self),
                 State::Achievements(achievements) => ui::achievements::show(ui, achievements, self),
                 State::Error(s) => ui::error::show(ui, s),
                 State::Game => ui::game::show(ui, self),
+                State::PullUrls(pull_urls) => ui::pull_urls::show(ui, pull_urls, self),
                 State::Pulls(url) => ui::pulls::show(ui, url, self),
+                State::PullHistory(history) => ui::pull_history::show(ui, history, self),
+                State::Archive(uids) => ui::archive::show(ui, uids, self),
+                State::Stats(stats) => ui::stats::show(ui, stats, self),
                 State::PullMenu => ui::pull_menu::show(ui, self),
+                State::History(snapshots, a, b) => ui::history::show(ui, snapshots, *a, *b, self),
+                State::Proxy(proxy) => ui::proxy::show(ui, proxy, self),
+                State::Logs(logs) => ui::logs::show(ui, logs, self),
+                State::ThemeEditor(editor) => ui::theme::show(ui, editor, self),
             }
         });
 
         self.toasts.show(ctx);
+
+        ui::focus::show(ctx);
+    }
+}
+
+fn upsert_user(users: &mut Vec<User>, user: User) {
+    match users
+        .iter_mut()
+        .find(|u| u.username.eq_ignore_ascii_case(&user.username))
+    {
+        Some(u) => *u = user,
+        None => users.push(user),
     }
 }
 
-#[cfg(not(debug_assertions))]
-fn update(message_tx: &mpsc::Sender<Message>) {
-    let message_tx = message_tx.clone();
-
-    thread::spawn(move || {
-        let status = self_update::backends::github::Update::configure()
-            .repo_owner("juliuskreutz")
-            .repo_name("stardb-exporter")
-            .bin_name("stardb-exporter")
-            .current_version(self_update::cargo_crate_version!())
-            .no_confirm(true)
-            .build()
-            .ok()
-            .and_then(|e| e.update().ok());
-
-        message_tx.send(Message::Updated(status)).unwrap();
-    });
+fn renew(stardb: &stardb::Client, user: &User, message_tx: &mpsc::Sender<Message>) {
+    let stardb = stardb.clone();
+    let id = user.id.clone();
+    let username = user.username.clone();
+
+    jobs::spawn(
+        message_tx,
+        t!("renew.job", username = username),
+        move |_| Ok(stardb.renew(&id)?),
+        move |result| match result {
+            Ok((id, username)) => Message::User(User { id, username }),
+            Err(e) if matches!(e.downcast_ref(), Some(stardb::Error::AuthExpired)) => {
+                tracing::warn!("The session of {username} expired");
+
+                Message::Expired(username, None)
+            }
+            Err(e) => {
+                // Keep the account, the next renew might get through
+                tracing::error!("Couldn't renew the cookie of {username}: {e}");
+
+                Message::Toast(egui_notify::Toast::warning(t!(
+                    "renew.failed",
+                    username = username
+                )))
+            }
+        },
+    );
 }
 
-#[cfg(debug_assertions)]
-fn update(message_tx: &mpsc::Sender<Message>) {
-    message_tx.send(Message::GoTo(State::Menu)).unwrap();
+/// Both egui themes get the colors, egui switches between them with the system theme and the
+/// style is only set again when the colors change
+fn set_style(ctx: &egui::Context, colors: themes::Colors) {
+    let style = colors.style();
+
+    ctx.set_style_of(egui::Theme::Dark, style.clone());
+    ctx.set_style_of(egui::Theme::Light, style);
 }
@@ -0,0 +1,151 @@
+use crate::{
+    gacha::{History, Pull},
+    games::Game,
+};
+
+/// Every pull ever downloaded, stored in `pulls/archive.sqlite` in the storage dir.
+/// HoYoverse only keeps six months of history, so this grows beyond what the api returns
+pub struct Archive {
+    connection: rusqlite::Connection,
+}
+
+impl Archive {
+    pub fn open() -> anyhow::Result<Self> {
+        let connection =
+            rusqlite::Connection::open(crate::storage_dir("pulls")?.join("archive.sqlite"))?;
+
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS accounts (
+                game TEXT NOT NULL,
+                uid TEXT NOT NULL,
+                lang TEXT NOT NULL,
+                timezone INTEGER NOT NULL,
+                PRIMARY KEY (game, uid)
+            );
+            CREATE TABLE IF NOT EXISTS pulls (
+                game TEXT NOT NULL,
+                uid TEXT NOT NULL,
+                id TEXT NOT NULL,
+                gacha_type TEXT NOT NULL,
+                gacha_id TEXT NOT NULL,
+                item_id TEXT NOT NULL,
+                count TEXT NOT NULL,
+                time TEXT NOT NULL,
+                name TEXT NOT NULL,
+                item_type TEXT NOT NULL,
+                rank_type TEXT NOT NULL,
+                PRIMARY KEY (game, uid, id)
+            );",
+        )?;
+
+        Ok(Self { connection })
+    }
+
+    pub fn contains(&self, game: Game, pull: &Pull) -> bool {
+        self.connection
+            .query_row(
+                "SELECT 1 FROM pulls WHERE game = ?1 AND uid = ?2 AND id = ?3",
+                (game.id(), &pull.uid, &pull.id),
+                |_| Ok(()),
+            )
+            .is_ok()
+    }
+
+    /// Adds the pulls that aren't archived yet and returns how many that were
+    pub fn insert(&mut self, history: &History) -> anyhow::Result<usize> {
+        let transaction = self.connection.transaction()?;
+
+        transaction.execute(
+            "INSERT OR REPLACE INTO accounts (game, uid, lang, timezone) VALUES (?1, ?2, ?3, ?4)",
+            (
+                history.game.id(),
+                &history.uid,
+                &history.lang,
+                history.timezone,
+            ),
+        )?;
+
+        let mut inserted = 0;
+
+        {
+            let mut statement = transaction.prepare(
+                "INSERT OR IGNORE INTO pulls
+                    (game, uid, id, gacha_type, gacha_id, item_id, count, time, name, item_type, rank_type)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            )?;
+
+            for pull in &history.pulls {
+                inserted += statement.execute((
+                    history.game.id(),
+                    &history.uid,
+                    &pull.id,
+                    &pull.gacha_type,
+                    &pull.gacha_id,
+                    &pull.item_id,
+                    &pull.count,
+                    &pull.time,
+                    &pull.name,
+                    &pull.item_type,
+                    &pull.rank_type,
+                ))?;
+            }
+        }
+
+        transaction.commit()?;
+
+        Ok(inserted)
+    }
+
+    pub fn uids(&self, game: Game) -> anyhow::Result<Vec<String>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT uid FROM accounts WHERE game = ?1 ORDER BY uid")?;
+
+        let uids = statement
+            .query_map([game.id()], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+
+        Ok(uids)
+    }
+
+    /// The archived history of an account, newest pull first
+    pub fn history(&self, game: Game, uid: &str) -> anyhow::Result<History> {
+        let (lang, timezone) = self.connection.query_row(
+            "SELECT lang, timezone FROM accounts WHERE game = ?1 AND uid = ?2",
+            (game.id(), uid),
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        // Ids are numeric strings that grow over time
+        let mut statement = self.connection.prepare(
+            "SELECT id, gacha_type, gacha_id, item_id, count, time, name, item_type, rank_type
+                FROM pulls WHERE game = ?1 AND uid = ?2
+                ORDER BY length(id) DESC, id DESC",
+        )?;
+
+        let pulls = statement
+            .query_map((game.id(), uid), |row| {
+                Ok(Pull {
+                    id: row.get(0)?,
+                    uid: uid.to_string(),
+                    gacha_type: row.get(1)?,
+                    gacha_id: row.get(2)?,
+                    item_id: row.get(3)?,
+                    count: row.get(4)?,
+                    time: row.get(5)?,
+                    name: row.get(6)?,
+                    item_type: row.get(7)?,
+                    rank_type: row.get(8)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+
+        Ok(History {
+            game,
+            uid: uid.to_string(),
+            lang,
+            timezone,
+            pulls,
+        })
+    }
+}
@@ -0,0 +1,86 @@
+use std::{fs, path::PathBuf};
+
+use crate::games::Game;
+
+/// Backups kept per account and game, older ones are deleted after each save
+const MAX_BACKUPS: usize = 10;
+
+/// Server side achievement state captured right before a sync.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct Backup {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub achievements: Vec<u32>,
+}
+
+pub fn save(game: Game, username: &str, achievements: &[u32]) -> anyhow::Result<()> {
+    let backup = Backup {
+        timestamp: chrono::Utc::now(),
+        achievements: achievements.to_vec(),
+    };
+
+    let mut path = crate::storage_dir("backups")?;
+    path.push(format!(
+        "{}{}.json",
+        prefix(game, username),
+        backup.timestamp.timestamp()
+    ));
+
+    fs::write(path, serde_json::to_vec(&backup)?)?;
+
+    let mut backups = list(game, username)?;
+    backups.sort_unstable_by_key(|(timestamp, _)| std::cmp::Reverse(*timestamp));
+    for (_, path) in backups.into_iter().skip(MAX_BACKUPS) {
+        fs::remove_file(path)?;
+    }
+
+    Ok(())
+}
+
+/// The newest backup of `username`, restoring one of another account would overwrite it
+pub fn latest(game: Game, username: &str) -> Option<Backup> {
+    let path = list(game, username)
+        .ok()?
+        .into_iter()
+        .max_by_key(|(timestamp, _)| *timestamp)?
+        .1;
+
+    serde_json::from_slice(&fs::read(path).ok()?).ok()
+}
+
+/// Every backup file of `username` with its timestamp
+fn list(game: Game, username: &str) -> anyhow::Result<Vec<(i64, PathBuf)>> {
+    let prefix = prefix(game, username);
+
+    Ok(crate::storage_dir("backups")?
+        .read_dir()?
+        .flat_map(|r| r.ok().map(|d| d.path()))
+        .filter_map(|p| {
+            let timestamp: i64 = p
+                .file_stem()?
+                .to_str()?
+                .strip_prefix(&prefix)?
+                .parse()
+                .ok()?;
+
+            Some((timestamp, p))
+        })
+        .collect())
+}
+
+/// Usernames are case insensitive and may contain characters that aren't allowed in file
+/// names, so everything else is escaped
+fn prefix(game: Game, username: &str) -> String {
+    let username: String = username
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c.to_string()
+            } else {
+                format!("%{:x}", c as u32)
+            }
+        })
+        .collect();
+
+    format!("{}_{username}_achievements_", game.id())
+}
@@ -0,0 +1,742 @@
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{Ipv4Addr, Shutdown, TcpListener, TcpStream},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use base64::prelude::*;
+use chacha20poly1305::aead::{OsRng, rand_core::RngCore};
+use serde_json::{Value, json};
+use sha1::{Digest, Sha1};
+
+use crate::{
+    app::{App, Message},
+    games::{self, Game},
+};
+
+pub const DEFAULT_PORT: u16 = 47831;
+
+/// How often the listener checks whether the server was stopped
+const ACCEPT_POLL: Duration = Duration::from_millis(50);
+
+/// Until a client has to have sent its request
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Every connection has its own thread and websockets stay open, so more are turned away
+const MAX_CONNECTIONS: usize = 16;
+
+/// Captures wait for the user to log into the game
+const RESULT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// Requests and websocket messages are commands, nothing big
+const MAX_BODY: usize = 16 * 1024;
+
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+#[derive(Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub enabled: bool,
+    pub port: u16,
+    /// Sent with every request, so only paired pages get an answer
+    pub token: String,
+    /// Pages and extensions that may connect, e.g. `chrome-extension://<id>`
+    pub origins: Vec<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_PORT,
+            token: String::new(),
+            origins: vec!["https://stardb.gg".to_string()],
+        }
+    }
+}
+
+pub fn new_token() -> String {
+    let mut bytes = [0; 24];
+    OsRng.fill_bytes(&mut bytes);
+
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// What a page asked the app to do
+pub enum Request {
+    Status(mpsc::Sender<Value>),
+    /// `None` for the game that is open in the app
+    Achievements(Option<Game>),
+    PullUrls(Option<Game>),
+}
+
+/// Sent to every connected page
+pub enum Event<'a> {
+    Achievements(Game, &'a [u32]),
+    PullUrls(Game, &'a [games::PullUrl]),
+    /// Name of the request that failed and why
+    Error(&'static str, Game, String),
+}
+
+impl Event<'_> {
+    fn json(&self) -> Value {
+        match self {
+            Event::Achievements(game, achievements) => json!({
+                "type": "achievements",
+                "game": game.id(),
+                "import": crate::export::achievements_import(*game, achievements),
+            }),
+            Event::PullUrls(game, pull_urls) => json!({
+                "type": "pull_urls",
+                "game": game.id(),
+                "urls": pull_urls.iter().map(|p| json!({
+                    "url": p.url,
+                    "timestamp": p.timestamp,
+                    "uid": p.uid,
+                    "region": p.region,
+                    "error": p.error,
+                })).collect::<Vec<_>>(),
+            }),
+            Event::Error(request, game, error) => json!({
+                "type": "error",
+                "request": request,
+                "game": game.id(),
+                "error": error,
+            }),
+        }
+    }
+}
+
+pub fn status(app: &App) -> Value {
+    json!({
+        "type": "status",
+        "version": env!("CARGO_PKG_VERSION"),
+        "game": app.game.id(),
+        "account": app.user().map(|u| &u.username),
+        "busy": !app.jobs.is_empty(),
+    })
+}
+
+/// Localhost http and websocket server for the stardb website and browser extensions
+pub struct Server {
+    shared: Arc<Shared>,
+    stop: Arc<AtomicBool>,
+    listener: Option<thread::JoinHandle<()>>,
+}
+
+struct Shared {
+    settings: Settings,
+    message_tx: mpsc::Sender<Message>,
+    ctx: egui::Context,
+    subscribers: Mutex<Vec<Subscriber>>,
+    next_id: AtomicU64,
+    connections: AtomicUsize,
+}
+
+/// A websocket or an http request waiting for a result
+struct Subscriber {
+    id: u64,
+    events: mpsc::Sender<Value>,
+    /// Websockets are closed together with the server
+    stream: Option<TcpStream>,
+}
+
+impl Server {
+    pub fn start(
+        settings: &Settings,
+        message_tx: &mpsc::Sender<Message>,
+        ctx: &egui::Context,
+    ) -> anyhow::Result<Self> {
+        if settings.token.len() < 16 {
+            return Err(anyhow::anyhow!("The pairing token is too short"));
+        }
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, settings.port))?;
+        listener.set_nonblocking(true)?;
+
+        tracing::info!("Companion server listening on 127.0.0.1:{}", settings.port);
+
+        let shared = Arc::new(Shared {
+            settings: settings.clone(),
+            message_tx: message_tx.clone(),
+            ctx: ctx.clone(),
+            subscribers: Mutex::new(Vec::new()),
+            next_id: AtomicU64::new(0),
+            connections: AtomicUsize::new(0),
+        });
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let listener = {
+            let shared = shared.clone();
+            let stop = stop.clone();
+
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    match listener.accept() {
+                        Ok((mut stream, _)) => {
+                            if shared.connections.fetch_add(1, Ordering::Relaxed) >= MAX_CONNECTIONS
+                            {
+                                shared.connections.fetch_sub(1, Ordering::Relaxed);
+
+                                let _ = stream
+                                    .set_nonblocking(false)
+                                    .and_then(|_| stream.set_write_timeout(Some(READ_TIMEOUT)));
+                                let _ = write_response(
+                                    &mut stream,
+                                    None,
+                                    Response::error(
+                                        "503 Service Unavailable",
+                                        "Too many connections",
+                                    ),
+                                );
+                                continue;
+                            }
+
+                            let shared = shared.clone();
+                            thread::spawn(move || {
+                                if let Err(e) = shared.connection(stream) {
+                                    tracing::debug!("Companion connection failed: {e}");
+                                }
+
+                                shared.connections.fetch_sub(1, Ordering::Relaxed);
+                            });
+                        }
+                        Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                            thread::sleep(ACCEPT_POLL)
+                        }
+                        Err(e) => tracing::warn!("Companion server couldn't accept: {e}"),
+                    }
+                }
+            })
+        };
+
+        Ok(Self {
+            shared,
+            stop,
+            listener: Some(listener),
+        })
+    }
+
+    pub fn broadcast(&self, event: Event) {
+        let event = event.json();
+
+        self.shared
+            .subscribers
+            .lock()
+            .unwrap()
+            .retain(|s| s.events.send(event.clone()).is_ok());
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+
+        // Frees the port before a restarted server binds it again
+        if let Some(listener) = self.listener.take() {
+            let _ = listener.join();
+        }
+
+        for subscriber in self.shared.subscribers.lock().unwrap().drain(..) {
+            if let Some(stream) = subscriber.stream {
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+        }
+    }
+}
+
+/// Reads a line of at most `limit` bytes, a longer one is never buffered
+fn read_line(reader: &mut impl BufRead, line: &mut String, limit: usize) -> anyhow::Result<usize> {
+    let size = reader.take(limit as u64).read_line(line)?;
+
+    if size == limit && !line.ends_with('\n') {
+        return Err(anyhow::anyhow!("Headers too large"));
+    }
+
+    Ok(size)
+}
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    query: String,
+    /// Names in lowercase
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl HttpRequest {
+    fn read(reader: &mut impl BufRead) -> anyhow::Result<Self> {
+        let mut line = String::new();
+        let mut size = read_line(reader, &mut line, MAX_BODY)?;
+
+        let mut parts = line.split_whitespace();
+        let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+            return Err(anyhow::anyhow!("Invalid request line"));
+        };
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+        let mut request = Self {
+            method: method.to_string(),
+            path: path.to_string(),
+            query: query.to_string(),
+            headers: Vec::new(),
+            body: Vec::new(),
+        };
+
+        loop {
+            line.clear();
+            size += read_line(reader, &mut line, MAX_BODY - size)?;
+
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+
+            if let Some((name, value)) = line.split_once(':') {
+                request
+                    .headers
+                    .push((name.trim().to_lowercase(), value.trim().to_string()));
+            }
+        }
+
+        let length: usize = request
+            .header("content-length")
+            .map(|l| l.parse())
+            .transpose()?
+            .unwrap_or(0);
+        if length > MAX_BODY {
+            return Err(anyhow::anyhow!("Body too large"));
+        }
+
+        request.body = vec![0; length];
+        reader.read_exact(&mut request.body)?;
+
+        Ok(request)
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn param(&self, name: &str) -> Option<&str> {
+        self.query
+            .split('&')
+            .filter_map(|p| p.split_once('='))
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| v)
+    }
+
+    /// `game` of the json body, missing for the game open in the app
+    fn game(&self) -> anyhow::Result<Option<Game>> {
+        if self.body.is_empty() {
+            return Ok(None);
+        }
+
+        let body: Value = serde_json::from_slice(&self.body)?;
+
+        parse_game(&body)
+    }
+}
+
+fn parse_game(value: &Value) -> anyhow::Result<Option<Game>> {
+    match value["game"].as_str() {
+        Some(id) => [Game::Hsr, Game::Gi, Game::Zzz]
+            .into_iter()
+            .find(|g| g.id() == id)
+            .map(Some)
+            .ok_or_else(|| anyhow::anyhow!("Unknown game {id}")),
+        None => Ok(None),
+    }
+}
+
+struct Response {
+    status: &'static str,
+    body: Option<Value>,
+}
+
+impl Response {
+    fn json(status: &'static str, body: Value) -> Self {
+        Self {
+            status,
+            body: Some(body),
+        }
+    }
+
+    fn error(status: &'static str, error: impl ToString) -> Self {
+        Self::json(status, json!({ "error": error.to_string() }))
+    }
+}
+
+impl Shared {
+    fn connection(self: &Arc<Self>, mut stream: TcpStream) -> anyhow::Result<()> {
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let request = HttpRequest::read(&mut reader)?;
+
+        // A page from an origin that isn't allowed can't even learn that the server exists
+        let origin = request.header("origin");
+        if let Some(origin) = origin
+            && !self.allowed(origin)
+        {
+            tracing::warn!("Companion server refused {origin}");
+            return write_response(
+                &mut stream,
+                None,
+                Response::error("403 Forbidden", "Origin"),
+            );
+        }
+
+        if !self.local_host(&request) {
+            return write_response(
+                &mut stream,
+                origin,
+                Response::error("403 Forbidden", "Host"),
+            );
+        }
+
+        // Preflights don't carry the token
+        if request.method == "OPTIONS" {
+            return write_response(
+                &mut stream,
+                origin,
+                Response {
+                    status: "204 No Content",
+                    body: None,
+                },
+            );
+        }
+
+        // Browsers can't set headers on websockets, so they send the token as a parameter
+        let token = request
+            .header("authorization")
+            .and_then(|a| a.strip_prefix("Bearer "))
+            .or(request.param("token"));
+        if !token.is_some_and(|t| self.token_matches(t)) {
+            return write_response(
+                &mut stream,
+                origin,
+                Response::error("401 Unauthorized", "Pairing token"),
+            );
+        }
+
+        let response = match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/events")
+                if request
+                    .header("upgrade")
+                    .is_some_and(|u| u.eq_ignore_ascii_case("websocket")) =>
+            {
+                return self.websocket(stream, reader, &request);
+            }
+            ("GET", "/status") => self.status(),
+            ("POST", "/achievements") => match request.game() {
+                Ok(game) => self.wait("achievements", Request::Achievements(game)),
+                Err(e) => Response::error("400 Bad Request", e),
+            },
+            ("POST", "/pull-urls") => match request.game() {
+                Ok(game) => self.wait("pull_urls", Request::PullUrls(game)),
+                Err(e) => Response::error("400 Bad Request", e),
+            },
+            _ => Response::error("404 Not Found", "Not found"),
+        };
+
+        write_response(&mut stream, origin, response)
+    }
+
+    fn allowed(&self, origin: &str) -> bool {
+        self.settings
+            .origins
+            .iter()
+            .map(|o| o.trim().trim_end_matches('/'))
+            .any(|o| !o.is_empty() && o.eq_ignore_ascii_case(origin))
+    }
+
+    /// Refuses other host names that resolve to 127.0.0.1, so dns rebinding doesn't get
+    /// around the origin check
+    fn local_host(&self, request: &HttpRequest) -> bool {
+        let port = self.settings.port;
+
+        request.header("host").is_some_and(|host| {
+            [format!("127.0.0.1:{port}"), format!("localhost:{port}")]
+                .iter()
+                .any(|h| h.eq_ignore_ascii_case(host))
+        })
+    }
+
+    fn token_matches(&self, token: &str) -> bool {
+        let expected = self.settings.token.as_bytes();
+
+        token.len() == expected.len()
+            && token
+                .bytes()
+                .zip(expected)
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+
+    fn send(&self, request: Request) -> anyhow::Result<()> {
+        self.message_tx
+            .send(Message::Companion(request))
+            .map_err(|_| anyhow::anyhow!("The exporter is closing"))?;
+
+        // The app only reads messages while it draws
+        self.ctx.request_repaint();
+
+        Ok(())
+    }
+
+    fn subscribe(&self, events: mpsc::Sender<Value>, stream: Option<TcpStream>) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+
+        self.subscribers
+            .lock()
+            .unwrap()
+            .push(Subscriber { id, events, stream });
+
+        id
+    }
+
+    fn unsubscribe(&self, id: u64) {
+        self.subscribers.lock().unwrap().retain(|s| s.id != id);
+    }
+
+    fn status(&self) -> Response {
+        let (tx, rx) = mpsc::channel();
+
+        if let Err(e) = self.send(Request::Status(tx)) {
+            return Response::error("503 Service Unavailable", e);
+        }
+
+        match rx.recv_timeout(READ_TIMEOUT) {
+            Ok(status) => Response::json("200 OK", status),
+            Err(_) => Response::error("504 Gateway Timeout", "The exporter didn't answer"),
+        }
+    }
+
+    /// Starts `request` and waits for the event of the same `kind`
+    fn wait(&self, kind: &str, request: Request) -> Response {
+        let (tx, rx) = mpsc::channel();
+        let id = self.subscribe(tx, None);
+
+        let response = match self.send(request) {
+            Ok(()) => {
+                let deadline = Instant::now() + RESULT_TIMEOUT;
+
+                loop {
+                    match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                        Ok(event) if event["type"] == kind => {
+                            break Response::json("200 OK", event);
+                        }
+                        Ok(event) if event["type"] == "error" && event["request"] == kind => {
+                            break Response::json("500 Internal Server Error", event);
+                        }
+                        Ok(_) => {}
+                        Err(mpsc::RecvTimeoutError::Timeout) => {
+                            break Response::error("504 Gateway Timeout", "No result in time");
+                        }
+                        Err(mpsc::RecvTimeoutError::Disconnected) => {
+                            break Response::error("503 Service Unavailable", "Server stopped");
+                        }
+                    }
+                }
+            }
+            Err(e) => Response::error("503 Service Unavailable", e),
+        };
+
+        self.unsubscribe(id);
+
+        response
+    }
+
+    /// Pushes every event to the page, which can send the same commands as json, e.g.
+    /// `{"type": "achievements", "game": "hsr"}`
+    fn websocket(
+        &self,
+        mut stream: TcpStream,
+        mut reader: BufReader<TcpStream>,
+        request: &HttpRequest,
+    ) -> anyhow::Result<()> {
+        let Some(key) = request.header("sec-websocket-key") else {
+            return write_response(
+                &mut stream,
+                request.header("origin"),
+                Response::error("400 Bad Request", "Missing websocket key"),
+            );
+        };
+
+        let accept = BASE64_STANDARD.encode(Sha1::digest(format!("{key}{WEBSOCKET_GUID}")));
+        write!(
+            stream,
+            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {accept}\r\n\r\n"
+        )?;
+
+        // Pages stay connected for as long as they like
+        stream.set_read_timeout(None)?;
+
+        let (tx, rx) = mpsc::channel::<Value>();
+        let id = self.subscribe(tx.clone(), Some(stream.try_clone()?));
+        let writer = Arc::new(Mutex::new(stream));
+
+        {
+            let writer = writer.clone();
+            thread::spawn(move || {
+                for event in rx {
+                    let mut writer = writer.lock().unwrap();
+
+                    if write_frame(&mut *writer, OPCODE_TEXT, event.to_string().as_bytes()).is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+
+        let result = (|| -> anyhow::Result<()> {
+            loop {
+                let (opcode, payload) = read_frame(&mut reader)?;
+
+                match opcode {
+                    OPCODE_TEXT => {
+                        if let Err(e) = self.command(&payload, &tx) {
+                            let _ = tx.send(json!({ "type": "error", "error": e.to_string() }));
+                        }
+                    }
+                    OPCODE_PING => {
+                        write_frame(&mut *writer.lock().unwrap(), OPCODE_PONG, &payload)?
+                    }
+                    OPCODE_CLOSE => {
+                        let _ = write_frame(&mut *writer.lock().unwrap(), OPCODE_CLOSE, &[]);
+                        return Ok(());
+                    }
+                    _ => {}
+                }
+            }
+        })();
+
+        self.unsubscribe(id);
+        let _ = writer.lock().unwrap().shutdown(Shutdown::Both);
+
+        result
+    }
+
+    fn command(&self, payload: &[u8], reply: &mpsc::Sender<Value>) -> anyhow::Result<()> {
+        let command: Value = serde_json::from_slice(payload)?;
+        let game = parse_game(&command)?;
+
+        let request = match command["type"].as_str() {
+            Some("status") => Request::Status(reply.clone()),
+            Some("achievements") => Request::Achievements(game),
+            Some("pull_urls") => Request::PullUrls(game),
+            _ => return Err(anyhow::anyhow!("Unknown command")),
+        };
+
+        self.send(request)
+    }
+}
+
+fn write_response(
+    stream: &mut TcpStream,
+    origin: Option<&str>,
+    response: Response,
+) -> anyhow::Result<()> {
+    let body = response.body.map(|b| b.to_string()).unwrap_or_default();
+
+    let mut head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\nVary: Origin\r\n",
+        response.status,
+        body.len()
+    );
+
+    // Only set for origins that passed the check
+    if let Some(origin) = origin {
+        head.push_str(&format!(
+            "Access-Control-Allow-Origin: {origin}\r\nAccess-Control-Allow-Methods: GET, POST\r\nAccess-Control-Allow-Headers: Authorization, Content-Type\r\nAccess-Control-Allow-Private-Network: true\r\nAccess-Control-Max-Age: 600\r\n"
+        ));
+    }
+
+    stream.write_all(format!("{head}\r\n{body}").as_bytes())?;
+    stream.flush()?;
+
+    Ok(())
+}
+
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xa;
+
+/// Reads one frame and unmasks it. Commands are small, so fragmented messages aren't
+/// put back together
+fn read_frame(reader: &mut impl Read) -> anyhow::Result<(u8, Vec<u8>)> {
+    let mut head = [0; 2];
+    reader.read_exact(&mut head)?;
+
+    let opcode = head[0] & 0x0f;
+    let masked = head[1] & 0x80 != 0;
+
+    let length = match head[1] & 0x7f {
+        126 => {
+            let mut length = [0; 2];
+            reader.read_exact(&mut length)?;
+            u16::from_be_bytes(length) as u64
+        }
+        127 => {
+            let mut length = [0; 8];
+            reader.read_exact(&mut length)?;
+            u64::from_be_bytes(length)
+        }
+        length => length as u64,
+    };
+
+    if length > MAX_BODY as u64 {
+        return Err(anyhow::anyhow!("Frame too large"));
+    }
+
+    let mut mask = [0; 4];
+    if masked {
+        reader.read_exact(&mut mask)?;
+    }
+
+    let mut payload = vec![0; length as usize];
+    reader.read_exact(&mut payload)?;
+
+    if masked {
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+    }
+
+    Ok((opcode, payload))
+}
+
+fn write_frame(writer: &mut impl Write, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    let mut frame = vec![0x80 | opcode];
+
+    match payload.len() {
+        length @ 0..=125 => frame.push(length as u8),
+        length @ 126..=0xffff => {
+            frame.push(126);
+            frame.extend((length as u16).to_be_bytes());
+        }
+        length => {
+            frame.push(127);
+            frame.extend((length as u64).to_be_bytes());
+        }
+    }
+
+    frame.extend(payload);
+
+    writer.write_all(&frame)?;
+    writer.flush()
+}
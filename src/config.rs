@@ -0,0 +1,323 @@
+use std::{fs, path::PathBuf, time::Duration};
+
+use serde_json::{Value, json};
+
+use crate::{app::SyncMode, companion, games::Game, http, i18n::Language, stardb, updater};
+
+/// Bumped whenever the layout of `Config` changes, with a matching step in `MIGRATIONS`
+pub const VERSION: u64 = 1;
+
+/// Upgrades a config of version `i` to version `i + 1`
+const MIGRATIONS: &[fn(Value) -> Value] = &[v0_to_v1];
+
+/// Ordered from least to most verbose
+#[derive(
+    Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 5] = [
+        LogLevel::Error,
+        LogLevel::Warn,
+        LogLevel::Info,
+        LogLevel::Debug,
+        LogLevel::Trace,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LogLevel::Error => "Error",
+            LogLevel::Warn => "Warn",
+            LogLevel::Info => "Info",
+            LogLevel::Debug => "Debug",
+            LogLevel::Trace => "Trace",
+        }
+    }
+
+    pub fn filter(self) -> tracing_subscriber::filter::LevelFilter {
+        use tracing_subscriber::filter::LevelFilter;
+
+        match self {
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+#[derive(Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct GameConfig {
+    /// Data dir of the game, used to find pull urls
+    pub path: Option<PathBuf>,
+    /// Username of the account the game syncs to
+    pub account: Option<String>,
+}
+
+/// Everything the user can configure, stored as `config.json` in the storage dir
+#[derive(Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub version: u64,
+    /// Names of the interfaces to capture on, empty for all connected ones
+    pub interfaces: Vec<String>,
+    /// Seconds until a request gives up
+    pub timeout: u64,
+    /// Overridden by `STARDB_URL`
+    pub stardb_url: String,
+    pub language: Language,
+    /// Zoom of the whole ui, also changed with ctrl + and ctrl -
+    pub ui_scale: f32,
+    pub log_level: LogLevel,
+    pub export_dir: Option<PathBuf>,
+    pub sync_mode: SyncMode,
+    pub proxy: http::Proxy,
+    pub updates: updater::Settings,
+    pub companion: companion::Settings,
+    pub hsr: GameConfig,
+    pub gi: GameConfig,
+    pub zzz: GameConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            version: VERSION,
+            interfaces: Vec::new(),
+            timeout: 30,
+            stardb_url: stardb::DEFAULT_URL.to_string(),
+            language: Language::default(),
+            ui_scale: 1.0,
+            log_level: LogLevel::default(),
+            export_dir: None,
+            sync_mode: SyncMode::default(),
+            proxy: http::Proxy::default(),
+            updates: updater::Settings::default(),
+            companion: companion::Settings::default(),
+            hsr: GameConfig::default(),
+            gi: GameConfig::default(),
+            zzz: GameConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Reads `config.json`, or the separate keys older versions kept in the eframe storage
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        let path = match path() {
+            Ok(path) => path,
+            Err(e) => {
+                tracing::error!("Couldn't find the config: {e}");
+                return Self::default();
+            }
+        };
+
+        let value = match fs::read(&path) {
+            Ok(bytes) => match serde_json::from_slice(&bytes) {
+                Ok(value) => value,
+                Err(e) => {
+                    tracing::error!("Invalid config, starting with defaults: {e}");
+                    let _ = fs::rename(&path, path.with_extension("json.invalid"));
+
+                    return Self::default();
+                }
+            },
+            Err(_) => match storage {
+                Some(storage) => legacy(storage),
+                None => return Self::default(),
+            },
+        };
+
+        match serde_json::from_value::<Self>(migrate(value)) {
+            Ok(config) => Self {
+                version: VERSION,
+                // Egui can't draw at a zoom of 0
+                ui_scale: if config.ui_scale.is_finite() {
+                    config.ui_scale.clamp(0.5, 3.0)
+                } else {
+                    1.0
+                },
+                ..config
+            },
+            Err(e) => {
+                tracing::error!("Couldn't read the config, starting with defaults: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        fs::write(path()?, serde_json::to_vec_pretty(self)?)?;
+
+        Ok(())
+    }
+
+    pub fn game(&self, game: Game) -> &GameConfig {
+        match game {
+            Game::Hsr => &self.hsr,
+            Game::Gi => &self.gi,
+            Game::Zzz => &self.zzz,
+        }
+    }
+
+    pub fn game_mut(&mut self, game: Game) -> &mut GameConfig {
+        match game {
+            Game::Hsr => &mut self.hsr,
+            Game::Gi => &mut self.gi,
+            Game::Zzz => &mut self.zzz,
+        }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout)
+    }
+
+    pub fn stardb_url(&self) -> String {
+        std::env::var("STARDB_URL").unwrap_or(self.stardb_url.clone())
+    }
+}
+
+fn path() -> anyhow::Result<PathBuf> {
+    let mut path = crate::storage_dir("config")?;
+    path.push("config.json");
+
+    Ok(path)
+}
+
+fn migrate(mut value: Value) -> Value {
+    let version = value["version"].as_u64().unwrap_or(0);
+
+    if version > VERSION {
+        tracing::warn!(
+            "Config version {version} is newer than this exporter, unknown settings are dropped"
+        );
+    }
+
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        tracing::info!("Migrating config from version {i} to {}", i + 1);
+        value = migration(value);
+    }
+
+    value
+}
+
+/// Version 0 are the separate eframe storage keys from before `config.json`
+fn legacy(storage: &dyn eframe::Storage) -> Value {
+    fn get<T: serde::de::DeserializeOwned + serde::Serialize>(
+        storage: &dyn eframe::Storage,
+        key: &str,
+    ) -> Value {
+        eframe::get_value::<T>(storage, key)
+            .and_then(|v| serde_json::to_value(v).ok())
+            .unwrap_or(Value::Null)
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct PerGame<T> {
+        zzz: Option<T>,
+        hsr: Option<T>,
+        gi: Option<T>,
+    }
+
+    json!({
+        "version": 0,
+        "accounts": get::<PerGame<String>>(storage, "accounts"),
+        "paths": get::<PerGame<PathBuf>>(storage, "paths"),
+        "sync_mode": get::<SyncMode>(storage, "sync_mode"),
+        "export_dir": get::<Option<PathBuf>>(storage, "export_dir"),
+        "proxy": get::<http::Proxy>(storage, "proxy"),
+        "updates": get::<updater::Settings>(storage, "updates"),
+    })
+}
+
+/// Moves the per game accounts and paths into one object per game
+fn v0_to_v1(value: Value) -> Value {
+    let mut config = json!({ "version": 1 });
+
+    for game in ["hsr", "gi", "zzz"] {
+        config[game] = json!({
+            "path": value["paths"][game],
+            "account": value["accounts"][game],
+        });
+    }
+
+    for key in ["sync_mode", "export_dir", "proxy", "updates"] {
+        if !value[key].is_null() {
+            config[key] = value[key].clone();
+        }
+    }
+
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_v0() {
+        let v0 = json!({
+            "version": 0,
+            "accounts": { "hsr": "Alice", "gi": null, "zzz": "Bob" },
+            "paths": { "hsr": "D:\\Star Rail\\Games\\StarRail_Data", "gi": null, "zzz": null },
+            "sync_mode": "Mirror",
+            "export_dir": null,
+            "proxy": {
+                "mode": "Custom",
+                "url": "http://127.0.0.1:8080",
+                "username": "",
+                "password": "",
+            },
+            "updates": null,
+        });
+
+        let value = migrate(v0);
+        assert_eq!(value["version"], VERSION);
+        assert!(value.get("accounts").is_none());
+        assert!(value.get("paths").is_none());
+        // Missing keys fall back to the defaults instead of null
+        assert!(value.get("updates").is_none());
+
+        let config: Config = serde_json::from_value(value).unwrap();
+        assert_eq!(config.version, VERSION);
+        assert_eq!(
+            config.hsr.path,
+            Some(PathBuf::from("D:\\Star Rail\\Games\\StarRail_Data"))
+        );
+        assert_eq!(config.hsr.account.as_deref(), Some("Alice"));
+        assert_eq!(config.gi.path, None);
+        assert_eq!(config.gi.account, None);
+        assert_eq!(config.zzz.account.as_deref(), Some("Bob"));
+        assert!(config.sync_mode == SyncMode::Mirror);
+        assert!(config.proxy.mode == http::ProxyMode::Custom);
+        assert_eq!(config.proxy.url, "http://127.0.0.1:8080");
+        assert!(config.updates == updater::Settings::default());
+        assert_eq!(config.timeout, Config::default().timeout);
+    }
+
+    #[test]
+    fn migrates_configs_without_version() {
+        let value = migrate(json!({ "accounts": { "gi": "Alice" } }));
+        assert_eq!(value["version"], VERSION);
+
+        let config: Config = serde_json::from_value(value).unwrap();
+        assert_eq!(config.gi.account.as_deref(), Some("Alice"));
+    }
+
+    #[test]
+    fn keeps_current_configs() {
+        let value = serde_json::to_value(Config::default()).unwrap();
+
+        assert_eq!(migrate(value.clone()), value);
+    }
+}
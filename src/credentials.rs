@@ -0,0 +1,111 @@
+use base64::prelude::*;
+use chacha20poly1305::{
+    ChaCha20Poly1305, KeyInit, Nonce,
+    aead::{Aead, AeadCore, OsRng, rand_core::RngCore},
+};
+
+use crate::app::User;
+
+#[derive(Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Protection {
+    /// Needs a passphrase on every start
+    Passphrase,
+    /// Bound to the machine id, unlocks automatically
+    Machine,
+}
+
+/// Saved accounts encrypted with ChaCha20-Poly1305
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct Encrypted {
+    pub protection: Protection,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+pub struct Key {
+    pub protection: Protection,
+    salt: [u8; 16],
+    key: chacha20poly1305::Key,
+}
+
+impl Key {
+    pub fn passphrase(passphrase: &str) -> anyhow::Result<Self> {
+        let mut salt = [0; 16];
+        OsRng.fill_bytes(&mut salt);
+
+        Self::derive(Protection::Passphrase, passphrase.as_bytes(), salt)
+    }
+
+    pub fn machine() -> anyhow::Result<Self> {
+        let mut salt = [0; 16];
+        OsRng.fill_bytes(&mut salt);
+
+        Self::derive(Protection::Machine, machine_id()?.as_bytes(), salt)
+    }
+
+    /// Decrypts saved accounts. The passphrase is ignored for machine protected accounts
+    pub fn unlock(encrypted: &Encrypted, passphrase: &str) -> anyhow::Result<(Self, Vec<User>)> {
+        let salt = BASE64_STANDARD
+            .decode(&encrypted.salt)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Invalid salt"))?;
+
+        let key = match encrypted.protection {
+            Protection::Passphrase => {
+                Self::derive(Protection::Passphrase, passphrase.as_bytes(), salt)?
+            }
+            Protection::Machine => {
+                Self::derive(Protection::Machine, machine_id()?.as_bytes(), salt)?
+            }
+        };
+
+        let nonce = BASE64_STANDARD.decode(&encrypted.nonce)?;
+        if nonce.len() != 12 {
+            return Err(anyhow::anyhow!("Invalid nonce"));
+        }
+
+        let ciphertext = BASE64_STANDARD.decode(&encrypted.ciphertext)?;
+
+        let plaintext = ChaCha20Poly1305::new(&key.key)
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| anyhow::anyhow!("Wrong passphrase"))?;
+
+        let users = serde_json::from_slice(&plaintext)?;
+
+        Ok((key, users))
+    }
+
+    pub fn encrypt(&self, users: &[User]) -> anyhow::Result<Encrypted> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+        let ciphertext = ChaCha20Poly1305::new(&self.key)
+            .encrypt(&nonce, serde_json::to_vec(users)?.as_slice())
+            .map_err(|_| anyhow::anyhow!("Couldn't encrypt accounts"))?;
+
+        Ok(Encrypted {
+            protection: self.protection,
+            salt: BASE64_STANDARD.encode(self.salt),
+            nonce: BASE64_STANDARD.encode(nonce),
+            ciphertext: BASE64_STANDARD.encode(ciphertext),
+        })
+    }
+
+    fn derive(protection: Protection, secret: &[u8], salt: [u8; 16]) -> anyhow::Result<Self> {
+        let mut key = chacha20poly1305::Key::default();
+
+        argon2::Argon2::default()
+            .hash_password_into(secret, &salt, &mut key)
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+
+        Ok(Self {
+            protection,
+            salt,
+            key,
+        })
+    }
+}
+
+fn machine_id() -> anyhow::Result<String> {
+    machine_uid::get().map_err(|e| anyhow::anyhow!("Couldn't read machine id: {e}"))
+}
@@ -0,0 +1,275 @@
+use std::collections::HashSet;
+
+use crate::{
+    gacha::{History, Pull},
+    games::Game,
+    i18n::t,
+    stardb::Achievement,
+    stats::Stats,
+};
+
+const EXPORT_APP: &str = "stardb-exporter";
+
+/// The json accepted by https://stardb.gg/import
+pub fn achievements_json(game: Game, achievements: &[u32]) -> String {
+    achievements_import(game, achievements).to_string()
+}
+
+/// `achievements_json` before it is serialized
+pub fn achievements_import(game: Game, achievements: &[u32]) -> serde_json::Value {
+    serde_json::json!({ game.achievements_key(): achievements })
+}
+
+pub fn achievements_csv(completed: &[u32], metadata: &[Achievement]) -> String {
+    let completed: HashSet<_> = completed.iter().copied().collect();
+
+    let mut csv = "id,name,series,status\n".to_string();
+
+    for achievement in metadata {
+        let status = if completed.contains(&achievement.id) {
+            "completed"
+        } else {
+            "missing"
+        };
+
+        csv.push_str(&format!(
+            "{},{},{},{status}\n",
+            achievement.id,
+            csv_field(&achievement.name),
+            csv_field(&achievement.series_name),
+        ));
+    }
+
+    csv
+}
+
+/// UIGF v4, which covers all three games
+pub fn uigf_v4(history: &History) -> anyhow::Result<String> {
+    // Required by UIGF v4, but Genshin's gacha log doesn't return it
+    if history.pulls.iter().any(|p| p.item_id.is_empty()) {
+        return Err(anyhow::anyhow!(t!("pull_history.no_item_ids")));
+    }
+
+    let section = match history.game {
+        Game::Gi => "hk4e",
+        Game::Hsr => "hkrpg",
+        Game::Zzz => "nap",
+    };
+
+    let list: Vec<_> = history
+        .pulls
+        .iter()
+        .map(|p| match history.game {
+            Game::Gi => gi_pull(p),
+            _ => pull(p),
+        })
+        .collect();
+
+    Ok(serde_json::json!({
+        "info": {
+            "export_timestamp": chrono::Utc::now().timestamp(),
+            "export_app": EXPORT_APP,
+            "export_app_version": env!("CARGO_PKG_VERSION"),
+            "version": "v4.0",
+        },
+        section: [{
+            "uid": history.uid,
+            "timezone": history.timezone,
+            "lang": history.lang,
+            "list": list,
+        }],
+    })
+    .to_string())
+}
+
+/// Legacy UIGF v3, Genshin only
+pub fn uigf_v3(history: &History) -> String {
+    let list: Vec<_> = history.pulls.iter().map(gi_pull).collect();
+
+    serde_json::json!({
+        "info": {
+            "uid": history.uid,
+            "lang": history.lang,
+            "export_timestamp": chrono::Utc::now().timestamp(),
+            "export_time": chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            "export_app": EXPORT_APP,
+            "export_app_version": env!("CARGO_PKG_VERSION"),
+            "uigf_version": "v3.0",
+            "region_time_zone": history.timezone,
+        },
+        "list": list,
+    })
+    .to_string()
+}
+
+/// Legacy SRGF v1, Star Rail only
+pub fn srgf(history: &History) -> String {
+    let list: Vec<_> = history.pulls.iter().map(pull).collect();
+
+    serde_json::json!({
+        "info": {
+            "srgf_version": "v1.0",
+            "uid": history.uid,
+            "lang": history.lang,
+            "region_time_zone": history.timezone,
+            "export_timestamp": chrono::Utc::now().timestamp(),
+            "export_app": EXPORT_APP,
+            "export_app_version": env!("CARGO_PKG_VERSION"),
+        },
+        "list": list,
+    })
+    .to_string()
+}
+
+fn pull(pull: &Pull) -> serde_json::Value {
+    serde_json::json!({
+        "gacha_id": pull.gacha_id,
+        "gacha_type": pull.gacha_type,
+        "item_id": pull.item_id,
+        "count": pull.count,
+        "time": pull.time,
+        "name": pull.name,
+        "item_type": pull.item_type,
+        "rank_type": pull.rank_type,
+        "id": pull.id,
+    })
+}
+
+fn gi_pull(pull: &Pull) -> serde_json::Value {
+    // Both character event banners share the pity and are merged in UIGF
+    let uigf_gacha_type = match pull.gacha_type.as_str() {
+        "400" => "301",
+        gacha_type => gacha_type,
+    };
+
+    serde_json::json!({
+        "uigf_gacha_type": uigf_gacha_type,
+        "gacha_type": pull.gacha_type,
+        "item_id": pull.item_id,
+        "count": pull.count,
+        "time": pull.time,
+        "name": pull.name,
+        "item_type": pull.item_type,
+        "rank_type": pull.rank_type,
+        "id": pull.id,
+    })
+}
+
+pub fn stats_csv(stats: &Stats) -> String {
+    let mut csv =
+        "banner,pulls,pity,four_stars,five_stars,average_pity,won_50_50,total_50_50,unknown_50_50\n"
+            .to_string();
+
+    for banner in &stats.banners {
+        let (won, total) = banner
+            .fifty_fifty()
+            .map(|(w, t)| (w.to_string(), t.to_string()))
+            .unwrap_or_default();
+
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{won},{total},{}\n",
+            csv_field(&banner.name),
+            banner.total,
+            banner.pity,
+            banner.four_stars,
+            banner.five_stars.len(),
+            banner
+                .average_pity()
+                .map(|a| format!("{a:.2}"))
+                .unwrap_or_default(),
+            banner.unknown_fifty_fifty,
+        ));
+    }
+
+    csv
+}
+
+pub fn file_name(game: Game, kind: &str, extension: &str) -> String {
+    format!(
+        "{}_{kind}_{}.{extension}",
+        game.id(),
+        chrono::Local::now().format("%Y%m%d_%H%M%S")
+    )
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(game: Game, gacha_type: &str, item_id: &str) -> History {
+        History {
+            game,
+            uid: "100000000".to_string(),
+            lang: "en-us".to_string(),
+            timezone: 8,
+            pulls: vec![Pull {
+                id: "1700000000000000001".to_string(),
+                uid: "100000000".to_string(),
+                gacha_type: gacha_type.to_string(),
+                gacha_id: "1001".to_string(),
+                item_id: item_id.to_string(),
+                count: "1".to_string(),
+                time: "2024-01-01 00:00:00".to_string(),
+                name: "Item".to_string(),
+                item_type: "Character".to_string(),
+                rank_type: "5".to_string(),
+            }],
+        }
+    }
+
+    /// The required fields of the UIGF v4 schema
+    fn assert_uigf_v4(json: &str, section: &str, item_fields: &[&str]) {
+        let json: serde_json::Value = serde_json::from_str(json).unwrap();
+
+        for field in [
+            "export_timestamp",
+            "export_app",
+            "export_app_version",
+            "version",
+        ] {
+            assert!(!json["info"][field].is_null(), "info.{field}");
+        }
+
+        let account = &json[section][0];
+        assert!(account["uid"].is_string() || account["uid"].is_number());
+        assert!(account["timezone"].is_number());
+
+        let item = &account["list"][0];
+        for field in item_fields {
+            let value = item[field].as_str().unwrap_or_default();
+            assert!(!value.is_empty(), "{section}.list.{field}");
+        }
+    }
+
+    #[test]
+    fn uigf_v4_required_fields() {
+        assert_uigf_v4(
+            &uigf_v4(&history(Game::Gi, "400", "10000089")).unwrap(),
+            "hk4e",
+            &["uigf_gacha_type", "gacha_type", "item_id", "time", "id"],
+        );
+        assert_uigf_v4(
+            &uigf_v4(&history(Game::Hsr, "11", "1205")).unwrap(),
+            "hkrpg",
+            &["gacha_id", "gacha_type", "item_id", "time", "id"],
+        );
+        assert_uigf_v4(
+            &uigf_v4(&history(Game::Zzz, "2001", "1191")).unwrap(),
+            "nap",
+            &["gacha_type", "item_id", "time", "id"],
+        );
+    }
+
+    #[test]
+    fn uigf_v4_refuses_missing_item_ids() {
+        assert!(uigf_v4(&history(Game::Gi, "301", "")).is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use ab_glyph::Font as _;
+
+/// Set once a fallback font was looked for, whether one was found or not
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Fonts that ship with the os and cover Chinese and Japanese, best first
+#[cfg(target_os = "windows")]
+const SYSTEM_FONTS: &[&str] = &[
+    "msyh.ttc",
+    "msyh.ttf",
+    "YuGothM.ttc",
+    "meiryo.ttc",
+    "simsun.ttc",
+    "msgothic.ttc",
+];
+
+#[cfg(target_os = "macos")]
+const SYSTEM_FONTS: &[&str] = &[
+    "/System/Library/Fonts/PingFang.ttc",
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    "/System/Library/Fonts/STHeiti Medium.ttc",
+    "/System/Library/Fonts/Supplemental/Arial Unicode.ttf",
+    "/Library/Fonts/Arial Unicode.ttf",
+];
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const SYSTEM_FONTS: &[&str] = &[
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-sans-cjk-vf-fonts/NotoSansCJK-VF.ttc",
+    "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+    "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+    "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+];
+
+/// Whether `text` has characters that Inter can't render
+pub fn needs_fallback(text: &str) -> bool {
+    text.chars().any(|c| {
+        matches!(c,
+            '\u{1100}'..='\u{11ff}'
+            | '\u{2e80}'..='\u{9fff}'
+            | '\u{ac00}'..='\u{d7af}'
+            | '\u{f900}'..='\u{faff}'
+            | '\u{ff00}'..='\u{ffef}'
+            | '\u{20000}'..='\u{3ffff}'
+        )
+    })
+}
+
+/// Loads a CJK font in the background the first time one of `texts` needs it. Cheap to
+/// call every frame
+pub fn ensure<'a>(ctx: &egui::Context, texts: impl IntoIterator<Item = &'a str>) {
+    if REQUESTED.load(Ordering::Relaxed) || !texts.into_iter().any(needs_fallback) {
+        return;
+    }
+
+    if REQUESTED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let ctx = ctx.clone();
+
+    std::thread::spawn(move || {
+        let Some((path, data)) = candidates().into_iter().find_map(|path| {
+            let data = fs::read(&path).ok()?;
+            usable(&data).then_some((path, data))
+        }) else {
+            tracing::warn!("No CJK font found, put one into the fonts folder of the storage dir");
+            return;
+        };
+
+        tracing::info!("Using {} as fallback font", path.display());
+
+        let families = [egui::FontFamily::Proportional, egui::FontFamily::Monospace]
+            .into_iter()
+            .map(|family| egui::epaint::text::InsertFontFamily {
+                family,
+                priority: egui::epaint::text::FontPriority::Lowest,
+            })
+            .collect();
+
+        ctx.add_font(egui::epaint::text::FontInsert::new(
+            "cjk",
+            egui::FontData::from_owned(data),
+            families,
+        ));
+        ctx.request_repaint();
+    });
+}
+
+/// Fonts the user put into the storage dir come before the ones of the os
+fn candidates() -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+
+    if let Ok(dir) = crate::storage_dir("fonts")
+        && let Ok(entries) = fs::read_dir(dir)
+    {
+        let mut user: Vec<_> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| {
+                p.extension()
+                    .and_then(|e| e.to_str())
+                    .is_some_and(|e| ["ttf", "otf", "ttc"].contains(&e.to_lowercase().as_str()))
+            })
+            .collect();
+        user.sort();
+
+        candidates.extend(user);
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let windir = std::env::var("WINDIR").unwrap_or("C:\\Windows".to_string());
+        let fonts = PathBuf::from(windir).join("Fonts");
+
+        candidates.extend(SYSTEM_FONTS.iter().map(|f| fonts.join(f)));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    candidates.extend(SYSTEM_FONTS.iter().map(PathBuf::from));
+
+    // Distros that keep fonts elsewhere, e.g. NixOS
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    if let Ok(output) = std::process::Command::new("fc-match")
+        .args(["--format=%{file}", ":lang=zh-cn"])
+        .output()
+        && output.status.success()
+    {
+        candidates.push(PathBuf::from(
+            String::from_utf8_lossy(&output.stdout).to_string(),
+        ));
+    }
+
+    candidates
+}
+
+/// Egui panics on fonts it can't parse, so check them first
+fn usable(data: &[u8]) -> bool {
+    ab_glyph::FontRef::try_from_slice(data).is_ok_and(|font| {
+        // 中 and あ, so the font covers both Chinese and Japanese
+        font.glyph_id('\u{4e2d}').0 != 0 && font.glyph_id('\u{3042}').0 != 0
+    })
+}
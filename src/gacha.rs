@@ -0,0 +1,468 @@
+use std::{collections::HashMap, fs, thread, time::Duration};
+
+use crate::{games::Game, i18n::t};
+
+const PAGE_SIZE: usize = 20;
+/// Pause between pages so the api doesn't answer with "visit too frequently"
+const PAGE_DELAY: Duration = Duration::from_millis(500);
+const RATE_LIMIT_DELAY: Duration = Duration::from_secs(5);
+const ATTEMPTS: u32 = 5;
+/// Partial downloads that weren't resumed for this long are deleted
+const PARTIAL_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Query parameters that are replaced while paging
+const PAGING_PARAMS: &[&str] = &[
+    "gacha_type",
+    "real_gacha_type",
+    "page",
+    "size",
+    "end_id",
+    "begin_id",
+];
+
+/// A single pull as returned by the gacha log api
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct Pull {
+    pub id: String,
+    #[serde(default)]
+    pub uid: String,
+    pub gacha_type: String,
+    /// Only returned for HSR and ZZZ
+    #[serde(default)]
+    pub gacha_id: String,
+    #[serde(default)]
+    pub item_id: String,
+    #[serde(default)]
+    pub count: String,
+    pub time: String,
+    pub name: String,
+    #[serde(default)]
+    pub item_type: String,
+    pub rank_type: String,
+}
+
+/// The whole downloaded pull history of an account, newest pull first per banner
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct History {
+    pub game: Game,
+    pub uid: String,
+    pub lang: String,
+    /// Utc offset of the server in hours, which `Pull::time` is relative to
+    pub timezone: i32,
+    pub pulls: Vec<Pull>,
+}
+
+/// A download that stopped halfway, stored as `pulls/<game>_<region>_partial.json` in the
+/// storage dir. The authkey changes with every url, so it's matched by the uid of the pulls
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Partial {
+    history: History,
+    /// Banners that are completely downloaded
+    done: Vec<String>,
+    /// The oldest pull id downloaded so far, per unfinished banner
+    end_ids: HashMap<String, String>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Server {
+    Global,
+    Cn,
+}
+
+/// What a gacha log url says about the account it belongs to
+pub struct UrlInfo {
+    pub server: Server,
+    pub region: Option<String>,
+    pub lang: Option<String>,
+    pub authkey_ver: Option<String>,
+}
+
+impl UrlInfo {
+    pub fn parse(url: &str) -> Self {
+        let region = query_param(url, "region").map(|r| r.to_string());
+        let game_biz = query_param(url, "game_biz");
+        let host = url
+            .split("://")
+            .nth(1)
+            .and_then(|u| u.split(['/', '?']).next())
+            .unwrap_or_default();
+
+        let cn = match game_biz {
+            Some(biz) => biz.ends_with("_cn"),
+            None => match &region {
+                Some(region) => region.starts_with("cn_") || region.ends_with("_cn"),
+                None => host.ends_with("mihoyo.com"),
+            },
+        };
+
+        Self {
+            server: if cn { Server::Cn } else { Server::Global },
+            region,
+            lang: query_param(url, "lang").map(|l| l.to_string()),
+            authkey_ver: query_param(url, "authkey_ver").map(|v| v.to_string()),
+        }
+    }
+
+    /// E.g. "Global, Europe"
+    pub fn name(&self) -> String {
+        let server = match self.server {
+            Server::Global => t!("region.global"),
+            Server::Cn => t!("region.cn"),
+        };
+
+        let region = match self.region.as_deref() {
+            Some("os_usa" | "prod_official_usa" | "prod_gf_us") => t!("region.america"),
+            Some("os_euro" | "prod_official_eur" | "prod_gf_eu") => t!("region.europe"),
+            Some("os_asia" | "prod_official_asia" | "prod_gf_jp") => t!("region.asia"),
+            Some("os_cht" | "prod_official_cht" | "prod_gf_sg") => t!("region.tw_hk_mo"),
+            Some("cn_gf01" | "prod_gf_cn") => t!("region.official"),
+            Some("cn_qd01" | "prod_qd_cn") => t!("region.bilibili"),
+            Some(region) => region.to_string(),
+            None => return server,
+        };
+
+        t!("region.server_region", server = server, region = region)
+    }
+}
+
+impl Game {
+    fn api_url(self, server: Server) -> &'static str {
+        match (self, server) {
+            (Game::Gi, Server::Global) => {
+                "https://public-operation-hk4e-sg.hoyoverse.com/gacha_info/api/getGachaLog"
+            }
+            (Game::Gi, Server::Cn) => {
+                "https://public-operation-hk4e.mihoyo.com/gacha_info/api/getGachaLog"
+            }
+            (Game::Hsr, Server::Global) => {
+                "https://public-operation-hkrpg-sg.hoyoverse.com/common/gacha_record/api/getGachaLog"
+            }
+            (Game::Hsr, Server::Cn) => {
+                "https://public-operation-hkrpg.mihoyo.com/common/gacha_record/api/getGachaLog"
+            }
+            (Game::Zzz, Server::Global) => {
+                "https://public-operation-nap-sg.hoyoverse.com/common/gacha_record/api/getGachaLog"
+            }
+            (Game::Zzz, Server::Cn) => {
+                "https://public-operation-nap.mihoyo.com/common/gacha_record/api/getGachaLog"
+            }
+        }
+    }
+}
+
+/// The url with its query sent to the api host of the server it belongs to
+pub fn api_url(game: Game, url: &str) -> String {
+    let url = url.split('#').next().unwrap_or_default();
+    let api_url = game.api_url(UrlInfo::parse(url).server);
+
+    match url.split_once('?') {
+        Some((_, query)) => format!("{api_url}?{query}"),
+        None => api_url.to_string(),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct Response {
+    retcode: i32,
+    #[serde(default)]
+    message: String,
+    data: Option<Page>,
+}
+
+#[derive(serde::Deserialize)]
+struct Page {
+    list: Vec<Pull>,
+    #[serde(default)]
+    region_time_zone: Option<i32>,
+}
+
+impl Pull {
+    /// The banner type this pull was downloaded from, as listed in `Game::banners`
+    pub fn banner(&self, game: Game) -> &str {
+        match game {
+            Game::Gi if self.gacha_type == "400" => "301",
+            // Zzz reports e.g. 2001 for the exclusive channel (2)
+            Game::Zzz => self.gacha_type.get(..1).unwrap_or_default(),
+            _ => &self.gacha_type,
+        }
+    }
+}
+
+impl Game {
+    /// The banner types to page through and the catalog keys of their names
+    pub fn banners(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Game::Hsr => &[
+                ("11", "banner.hsr_character_event"),
+                ("12", "banner.hsr_light_cone_event"),
+                ("21", "banner.hsr_character_collaboration"),
+                ("22", "banner.hsr_light_cone_collaboration"),
+                ("1", "banner.hsr_stellar"),
+                ("2", "banner.hsr_departure"),
+            ],
+            // 301 also returns the pulls of the second character banner (400)
+            Game::Gi => &[
+                ("301", "banner.gi_character_event"),
+                ("302", "banner.gi_weapon_event"),
+                ("500", "banner.gi_chronicled"),
+                ("200", "banner.gi_standard"),
+                ("100", "banner.gi_beginners"),
+            ],
+            Game::Zzz => &[
+                ("2", "banner.zzz_exclusive"),
+                ("3", "banner.zzz_w_engine"),
+                ("5", "banner.zzz_bangboo"),
+                ("1", "banner.zzz_stable"),
+            ],
+        }
+    }
+
+    fn banner_param(self) -> &'static str {
+        match self {
+            Game::Zzz => "real_gacha_type",
+            _ => "gacha_type",
+        }
+    }
+}
+
+/// Pages through every banner behind a gacha log url, stopping at the first pull `known`
+/// returns true for. Progress is reported as the banner name key and the number of pulls
+/// downloaded so far, an error from `progress` stops the download. A download that fails is
+/// resumed by the next call for the same account, even with a new authkey
+pub fn download(
+    agent: &ureq::Agent,
+    game: Game,
+    url: &str,
+    known: impl Fn(&Pull) -> bool,
+    mut progress: impl FnMut(&str, usize) -> anyhow::Result<()>,
+) -> anyhow::Result<History> {
+    let base_url = base_url(&api_url(game, url));
+    let lang = query_param(url, "lang").unwrap_or("en-us");
+    let key = partial_key(game, url);
+
+    remove_stale_partials();
+
+    let mut partial = load_partial(&key)
+        .filter(|p| p.history.lang == lang)
+        .unwrap_or_else(|| Partial::new(game, lang));
+
+    for (banner, name) in game.banners() {
+        if partial.done.iter().any(|b| b == banner) {
+            continue;
+        }
+
+        loop {
+            let end_id = partial.end_ids.get(*banner).map_or("0", |id| id.as_str());
+            let page_url = format!(
+                "{base_url}&{}={banner}&size={PAGE_SIZE}&end_id={end_id}",
+                game.banner_param()
+            );
+
+            let page = progress(name, partial.history.pulls.len())
+                .and_then(|_| fetch_page(agent, &page_url));
+
+            let page = match page {
+                Ok(page) => page,
+                Err(e) => {
+                    save_partial(&key, &partial);
+                    return Err(e);
+                }
+            };
+
+            // The saved download was of another account on the same region
+            if let Some(first) = page.list.first()
+                && !partial.history.uid.is_empty()
+                && first.uid != partial.history.uid
+            {
+                tracing::info!("Discarding the partial pull download of another account");
+
+                remove_partial(&key);
+                return download(agent, game, url, known, progress);
+            }
+
+            if let Some(timezone) = page.region_time_zone {
+                partial.history.timezone = timezone;
+            }
+
+            let Some(last) = page.list.last() else {
+                break;
+            };
+
+            partial.end_ids.insert(banner.to_string(), last.id.clone());
+
+            if partial.history.uid.is_empty() {
+                partial.history.uid = last.uid.clone();
+            }
+
+            let full = page.list.len() == PAGE_SIZE;
+
+            let mut list = page.list;
+            let reached_known = match list.iter().position(&known) {
+                Some(i) => {
+                    list.truncate(i);
+                    true
+                }
+                None => false,
+            };
+
+            partial.history.pulls.extend(list);
+
+            if !full || reached_known {
+                break;
+            }
+
+            thread::sleep(PAGE_DELAY);
+        }
+
+        partial.done.push(banner.to_string());
+        partial.end_ids.remove(*banner);
+    }
+
+    // Genshin doesn't report the timezone, so it's derived from the server the uid is on
+    if game == Game::Gi {
+        partial.history.timezone = match partial.history.uid.chars().next() {
+            Some('6') => -5,
+            Some('7') => 1,
+            _ => 8,
+        };
+    }
+
+    remove_partial(&key);
+
+    Ok(partial.history)
+}
+
+fn fetch_page(agent: &ureq::Agent, url: &str) -> anyhow::Result<Page> {
+    for attempt in 1..=ATTEMPTS {
+        let mut response = agent.get(url).call()?;
+        let response: Response = response.body_mut().read_json()?;
+
+        match response.retcode {
+            0 => {
+                return response
+                    .data
+                    .ok_or_else(|| anyhow::anyhow!("Empty gacha log response"));
+            }
+            // Visit too frequently
+            -110 if attempt < ATTEMPTS => {
+                tracing::warn!("Gacha log rate limited (attempt {attempt}/{ATTEMPTS})");
+                thread::sleep(RATE_LIMIT_DELAY);
+            }
+            retcode => {
+                return Err(anyhow::anyhow!(
+                    "Gacha log error {retcode}: {}",
+                    response.message
+                ));
+            }
+        }
+    }
+
+    Err(anyhow::anyhow!("Gacha log rate limited"))
+}
+
+/// The url without the paging parameters
+fn base_url(url: &str) -> String {
+    let Some((path, query)) = url.split_once('?') else {
+        return format!("{url}?");
+    };
+
+    let query: Vec<_> = query
+        .split('&')
+        .filter(|p| {
+            let key = p.split('=').next().unwrap_or_default();
+            !p.is_empty() && !PAGING_PARAMS.contains(&key)
+        })
+        .collect();
+
+    format!("{path}?{}", query.join("&"))
+}
+
+fn query_param<'a>(url: &'a str, key: &str) -> Option<&'a str> {
+    url.split_once('?')?
+        .1
+        .split('&')
+        .filter_map(|p| p.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| v)
+}
+
+impl Partial {
+    fn new(game: Game, lang: &str) -> Self {
+        Self {
+            history: History {
+                game,
+                uid: String::new(),
+                lang: lang.to_string(),
+                timezone: 0,
+                pulls: Vec::new(),
+            },
+            done: Vec::new(),
+            end_ids: HashMap::new(),
+        }
+    }
+}
+
+/// Accounts on one region can only be told apart by the pulls, the region keeps downloads of
+/// different servers apart
+fn partial_key(game: Game, url: &str) -> String {
+    let region: String = query_param(url, "region")
+        .unwrap_or("default")
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect();
+
+    format!("{}_{region}", game.id())
+}
+
+fn load_partial(key: &str) -> Option<Partial> {
+    let path = crate::storage_dir("pulls")
+        .ok()?
+        .join(format!("{key}_partial.json"));
+
+    serde_json::from_slice(&fs::read(path).ok()?).ok()
+}
+
+fn remove_partial(key: &str) {
+    if let Ok(dir) = crate::storage_dir("pulls") {
+        let _ = fs::remove_file(dir.join(format!("{key}_partial.json")));
+    }
+}
+
+/// Also cleans up partials of accounts that are never downloaded again
+fn remove_stale_partials() {
+    let Ok(entries) = crate::storage_dir("pulls").and_then(|d| Ok(d.read_dir()?)) else {
+        return;
+    };
+
+    for path in entries.flat_map(|r| r.ok().map(|d| d.path())) {
+        let stale = path
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|m| m.elapsed().ok())
+            .is_some_and(|age| age > PARTIAL_MAX_AGE);
+
+        if stale
+            && path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.ends_with("_partial.json"))
+        {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+fn save_partial(key: &str, partial: &Partial) {
+    let result = crate::storage_dir("pulls").and_then(|dir| {
+        fs::write(
+            dir.join(format!("{key}_partial.json")),
+            serde_json::to_vec(partial)?,
+        )?;
+        Ok(())
+    });
+
+    if let Err(e) = result {
+        tracing::error!("Couldn't save partial pull download: {e}");
+    }
+}
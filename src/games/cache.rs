@@ -0,0 +1,292 @@
+//! Reader for the Chromium blockfile disk cache the games' embedded browser writes to
+//! `webCaches/<version>/Cache/Cache_Data`
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+const INDEX_MAGIC: u32 = 0xC103CAC3;
+const BLOCK_MAGIC: u32 = 0xC104CAC3;
+const INDEX_HEADER_SIZE: usize = 368;
+const BLOCK_HEADER_SIZE: usize = 8192;
+const DEFAULT_TABLE_LEN: usize = 0x10000;
+
+const ENTRY_SIZE: usize = 256;
+/// Entries with long inline keys take up to four blocks
+const MAX_ENTRY_SIZE: usize = 4 * ENTRY_SIZE;
+const ENTRY_KEY_OFFSET: usize = 96;
+
+/// Microseconds between 1601-01-01, the base of Chromium timestamps, and the unix epoch
+const EPOCH_DELTA: i64 = 11_644_473_600_000_000;
+
+/// A cached url and the time its entry was created
+pub struct Entry {
+    pub url: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+/// Reads every entry of the cache in `dir`. Unreadable entries are skipped
+pub fn entries(dir: &Path) -> anyhow::Result<Vec<Entry>> {
+    let index = fs::read(dir.join("index"))?;
+
+    let mut cache = Cache {
+        dir: dir.to_path_buf(),
+        files: HashMap::new(),
+    };
+
+    cache
+        .entries(&index)
+        .ok_or_else(|| anyhow::anyhow!("{} is not a cache index", dir.display()))
+}
+
+struct Cache {
+    dir: PathBuf,
+    files: HashMap<String, Option<Vec<u8>>>,
+}
+
+impl Cache {
+    fn entries(&mut self, index: &[u8]) -> Option<Vec<Entry>> {
+        if index.len() < INDEX_HEADER_SIZE || u32_at(index, 0) != Some(INDEX_MAGIC) {
+            return None;
+        }
+
+        let table_len = match u32_at(index, 28).unwrap_or_default() as usize {
+            0 => DEFAULT_TABLE_LEN,
+            len => len,
+        };
+
+        let mut entries = Vec::new();
+
+        for bucket in 0..table_len {
+            let Some(mut addr) = u32_at(index, INDEX_HEADER_SIZE + bucket * 4) else {
+                break;
+            };
+
+            // Collisions are chained through `next`. The limit guards against corrupted loops
+            for _ in 0..64 {
+                let Some(entry) = self.read(addr, MAX_ENTRY_SIZE) else {
+                    break;
+                };
+
+                if let Some(e) = self.entry(&entry) {
+                    entries.push(e);
+                }
+
+                addr = u32_at(&entry, 4).unwrap_or_default();
+            }
+        }
+
+        Some(entries)
+    }
+
+    fn entry(&mut self, entry: &[u8]) -> Option<Entry> {
+        let creation_time = i64::from_le_bytes(entry.get(24..32)?.try_into().ok()?);
+        let key_len = u32_at(entry, 32)? as usize;
+        let long_key = u32_at(entry, 36)?;
+
+        let key = if long_key != 0 {
+            self.read(long_key, key_len)?
+        } else {
+            let end = (ENTRY_KEY_OFFSET + key_len).min(entry.len());
+            entry.get(ENTRY_KEY_OFFSET..end)?.to_vec()
+        };
+
+        let key = String::from_utf8_lossy(&key);
+        let url = key
+            .split(|c: char| c.is_whitespace() || c == '\0')
+            .filter_map(|s| s.find("https://").map(|i| &s[i..]))
+            .next_back()?
+            .to_string();
+
+        let micros = creation_time.checked_sub(EPOCH_DELTA)?;
+        let timestamp = chrono::DateTime::from_timestamp_micros(micros)?;
+
+        Some(Entry { url, timestamp })
+    }
+
+    /// Reads `len` bytes at a cache address, either from a `data_N` block file or an
+    /// external `f_XXXXXX` file
+    fn read(&mut self, addr: u32, len: usize) -> Option<Vec<u8>> {
+        if addr & 0x8000_0000 == 0 {
+            return None;
+        }
+
+        let file_type = (addr >> 28) & 0x7;
+
+        if file_type == 0 {
+            let name = format!("f_{:06x}", addr & 0x0fff_ffff);
+            let file = self.file(&name)?;
+
+            return Some(file[..len.min(file.len())].to_vec());
+        }
+
+        let block_size = match file_type {
+            2 => 256,
+            3 => 1024,
+            4 => 4096,
+            _ => return None,
+        };
+
+        let blocks = ((addr >> 24) & 0x3) as usize + 1;
+        let selector = (addr >> 16) & 0xff;
+        let start = (addr & 0xffff) as usize;
+
+        let file = self.file(&format!("data_{selector}"))?;
+
+        if u32_at(file, 0) != Some(BLOCK_MAGIC) {
+            return None;
+        }
+
+        let offset = BLOCK_HEADER_SIZE + start * block_size;
+        let len = len.min(blocks * block_size);
+
+        file.get(offset..offset + len).map(|b| b.to_vec())
+    }
+
+    fn file(&mut self, name: &str) -> Option<&Vec<u8>> {
+        self.files
+            .entry(name.to_string())
+            .or_insert_with(|| fs::read(self.dir.join(name)).ok())
+            .as_ref()
+    }
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://public-operation-hkrpg-sg.hoyoverse.com/common/gacha_record/api/getGachaLog?authkey=abc";
+    /// 2024-01-01 00:00:00 UTC
+    const CREATED: i64 = 1_704_067_200_000_000 + EPOCH_DELTA;
+
+    fn cache(files: Vec<(&str, Vec<u8>)>) -> Cache {
+        Cache {
+            dir: PathBuf::new(),
+            files: files
+                .into_iter()
+                .map(|(name, file)| (name.to_string(), Some(file)))
+                .collect(),
+        }
+    }
+
+    fn index(buckets: &[u32]) -> Vec<u8> {
+        let mut index = vec![0; INDEX_HEADER_SIZE];
+        index[0..4].copy_from_slice(&INDEX_MAGIC.to_le_bytes());
+        index[28..32].copy_from_slice(&(buckets.len() as u32).to_le_bytes());
+
+        for bucket in buckets {
+            index.extend_from_slice(&bucket.to_le_bytes());
+        }
+
+        index
+    }
+
+    /// One block of `data_1` with an inline key, or a key in `long_key`
+    fn entry(next: u32, key: &str, long_key: u32) -> Vec<u8> {
+        let mut entry = vec![0; ENTRY_SIZE];
+        entry[4..8].copy_from_slice(&next.to_le_bytes());
+        entry[24..32].copy_from_slice(&CREATED.to_le_bytes());
+        entry[32..36].copy_from_slice(&(key.len() as u32).to_le_bytes());
+        entry[36..40].copy_from_slice(&long_key.to_le_bytes());
+
+        if long_key == 0 {
+            entry[ENTRY_KEY_OFFSET..ENTRY_KEY_OFFSET + key.len()].copy_from_slice(key.as_bytes());
+        }
+
+        entry
+    }
+
+    fn data(blocks: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = vec![0; BLOCK_HEADER_SIZE];
+        data[0..4].copy_from_slice(&BLOCK_MAGIC.to_le_bytes());
+
+        for block in blocks {
+            data.extend_from_slice(block);
+        }
+
+        data
+    }
+
+    /// Address of a 256 byte block in `data_1`
+    fn addr(block: u32) -> u32 {
+        0x8000_0000 | (2 << 28) | (1 << 16) | block
+    }
+
+    #[test]
+    fn reads_chained_entries() {
+        let key = format!("1/0/_dk_https://hoyoverse.com {URL}");
+        let data = data(&[
+            entry(addr(1), "1/0/https://example.com/a.png", 0),
+            entry(0, &key, 0),
+        ]);
+
+        let entries = cache(vec![("data_1", data)])
+            .entries(&index(&[addr(0)]))
+            .unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].url, "https://example.com/a.png");
+        assert_eq!(entries[1].url, URL);
+        assert_eq!(entries[1].timestamp.timestamp(), 1_704_067_200);
+    }
+
+    #[test]
+    fn reads_long_keys() {
+        let data = data(&[entry(0, URL, 0x8000_0001)]);
+        let key = URL.as_bytes().to_vec();
+
+        let entries = cache(vec![("data_1", data), ("f_000001", key)])
+            .entries(&index(&[addr(0)]))
+            .unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].url, URL);
+    }
+
+    #[test]
+    fn skips_truncated_data() {
+        let mut data = data(&[entry(addr(1), URL, 0), entry(0, URL, 0)]);
+        data.truncate(BLOCK_HEADER_SIZE + ENTRY_SIZE + 100);
+
+        let entries = cache(vec![("data_1", data)])
+            .entries(&index(&[addr(0)]))
+            .unwrap();
+
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn skips_out_of_range_addresses() {
+        let data = data(&[entry(addr(500), URL, 0)]);
+
+        let entries = cache(vec![("data_1", data)])
+            .entries(&index(&[addr(0), addr(1000), 0x8000_0000 | (7 << 28), 0]))
+            .unwrap();
+
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn rejects_truncated_index() {
+        let mut index = index(&[addr(0)]);
+        assert!(cache(vec![]).entries(&index[..100]).is_none());
+
+        // A table shorter than its header says is read as far as it goes
+        index[28..32].copy_from_slice(&16u32.to_le_bytes());
+        let entries = cache(vec![("data_1", data(&[entry(0, URL, 0)]))])
+            .entries(&index)
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+
+        index[0] = 0;
+        assert!(cache(vec![]).entries(&index).is_none());
+    }
+}
@@ -1,26 +1,23 @@
-use std::{
-    collections::HashMap,
-    fs::File,
-    io::{BufRead, BufReader},
-    path::PathBuf,
-    sync::mpsc,
-};
+use std::{collections::HashMap, path::PathBuf, sync::mpsc};
 
 use auto_artifactarium::{GamePacket, GameSniffer, matches_achievement_packet};
 use base64::prelude::*;
 
+use crate::jobs;
+
 use regex::Regex;
 
 pub fn sniff(
     achievement_ids: &[u32],
     device_rx: &mpsc::Receiver<Vec<u8>>,
+    context: &jobs::Context,
 ) -> anyhow::Result<Vec<u32>> {
     let keys = load_keys()?;
     let mut sniffer = GameSniffer::new().set_initial_keys(keys);
 
     let mut achievements = Vec::new();
 
-    while let Ok(data) = device_rx.recv() {
+    while let Some(data) = super::next_packet(device_rx, context)? {
         let Some(GamePacket::Commands(commands)) = sniffer.receive_packet(data) else {
             continue;
         };
@@ -68,36 +65,13 @@ fn load_keys() -> anyhow::Result<HashMap<u16, Vec<u8>>> {
 }
 
 pub fn game_path() -> anyhow::Result<PathBuf> {
-    let mut log_path = PathBuf::from(&std::env::var("APPDATA")?);
-    log_path.pop();
-    log_path.push("LocalLow");
-    log_path.push("miHoYo");
-
-    let mut log_path_cn = log_path.clone();
-
-    log_path.push("Genshin Impact");
-    log_path_cn.push("原神");
-
-    log_path.push("output_log.txt");
-    log_path_cn.push("output_log.txt");
-
-    let log_path = match (log_path.exists(), log_path_cn.exists()) {
-        (true, _) => log_path,
-        (_, true) => log_path_cn,
-        _ => return Err(anyhow::anyhow!("Can't find log file")),
-    };
-
     let re = Regex::new(r".:\\.+(GenshinImpact_Data|YuanShen_Data)")?;
 
-    for line in BufReader::new(File::open(log_path)?).lines() {
-        let Ok(line) = line else {
-            break;
-        };
-
-        if let Some(m) = re.find(&line) {
-            return Ok(PathBuf::from(m.as_str()));
-        }
-    }
-
-    Err(anyhow::anyhow!("Couldn't find game path"))
+    super::game_path_from_logs(
+        &[
+            "miHoYo/Genshin Impact/output_log.txt",
+            "miHoYo/原神/output_log.txt",
+        ],
+        |line| re.find(line).map(|m| m.as_str()),
+    )
 }
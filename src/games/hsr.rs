@@ -1,17 +1,14 @@
-use std::{
-    collections::HashMap,
-    fs::File,
-    io::{BufRead, BufReader},
-    path::PathBuf,
-    sync::mpsc,
-};
+use std::{collections::HashMap, path::PathBuf, sync::mpsc};
 
 use auto_reliquary::{GamePacket, GameSniffer, matches_achievement_packet};
 use base64::prelude::*;
 
+use crate::jobs;
+
 pub fn sniff(
     achievement_ids: &[u32],
     device_rx: &mpsc::Receiver<Vec<u8>>,
+    context: &jobs::Context,
 ) -> anyhow::Result<Vec<u32>> {
     let keys = load_keys()?;
 
@@ -19,7 +16,7 @@ pub fn sniff(
 
     let mut achievements = Vec::new();
 
-    while let Ok(data) = device_rx.recv() {
+    while let Some(data) = super::next_packet(device_rx, context)? {
         let Some(GamePacket::Commands(commands)) = sniffer.receive_packet(data) else {
             continue;
         };
@@ -67,40 +64,15 @@ fn load_keys() -> anyhow::Result<HashMap<u32, Vec<u8>>> {
 }
 
 pub fn game_path() -> anyhow::Result<PathBuf> {
-    let mut log_path = PathBuf::from(&std::env::var("APPDATA")?);
-    log_path.pop();
-    log_path.push("LocalLow");
-
-    let mut log_path_cn = log_path.clone();
-
-    log_path.push("Cognosphere");
-    log_path_cn.push("miHoYo");
-
-    log_path.push("Star Rail");
-    log_path_cn.push("崩坏：星穹铁道");
+    let mut path = super::game_path_from_logs(
+        &[
+            "Cognosphere/Star Rail/Player.log",
+            "miHoYo/崩坏：星穹铁道/Player.log",
+        ],
+        |line| line.strip_prefix("Loading player data from "),
+    )?;
 
-    log_path.push("Player.log");
-    log_path_cn.push("Player.log");
-
-    let log_path = match (log_path.exists(), log_path_cn.exists()) {
-        (true, _) => log_path,
-        (_, true) => log_path_cn,
-        _ => return Err(anyhow::anyhow!("Can't find log file")),
-    };
-
-    for line in BufReader::new(File::open(log_path)?).lines() {
-        let Ok(line) = line else {
-            break;
-        };
-
-        if let Some(line) = line.strip_prefix("Loading player data from ") {
-            let mut path = PathBuf::from(line);
-
-            path.pop();
-
-            return Ok(path);
-        }
-    }
+    path.pop();
 
-    Err(anyhow::anyhow!("Couldn't find game path"))
+    Ok(path)
 }
@@ -1,17 +1,78 @@
+mod cache;
 mod gi;
 mod hsr;
+mod wine;
 mod zzz;
 
 use std::{
+    fs::File,
+    io::{BufRead, BufReader},
     path::{Path, PathBuf},
-    sync::mpsc,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
     thread,
+    time::Duration,
 };
 
-use crate::app::{Message, State};
+use crate::{
+    app::{Achievements, Message, State},
+    gacha, history,
+    i18n::t,
+    jobs, stardb,
+};
 use regex::Regex;
 
-#[derive(Clone, Copy, PartialEq)]
+/// How often a capture and its devices check whether it was cancelled
+const PACKET_POLL: Duration = Duration::from_millis(200);
+
+/// Per device counters of this session, included in diagnostics bundles
+static CAPTURE_STATS: Mutex<Vec<CaptureStats>> = Mutex::new(Vec::new());
+
+#[derive(Clone)]
+pub struct CaptureStats {
+    pub device: String,
+    pub packets: u64,
+    /// Dropped by pcap and by the interface, as reported by pcap
+    pub dropped: u32,
+    pub if_dropped: u32,
+    pub restarts: u32,
+}
+
+pub fn capture_stats() -> Vec<CaptureStats> {
+    CAPTURE_STATS.lock().unwrap().clone()
+}
+
+fn record_capture(device: &str, f: impl FnOnce(&mut CaptureStats)) {
+    let mut stats = CAPTURE_STATS.lock().unwrap();
+
+    let i = match stats.iter().position(|s| s.device == device) {
+        Some(i) => i,
+        None => {
+            stats.push(CaptureStats {
+                device: device.to_string(),
+                packets: 0,
+                dropped: 0,
+                if_dropped: 0,
+                restarts: 0,
+            });
+            stats.len() - 1
+        }
+    };
+
+    f(&mut stats[i]);
+}
+
+/// A network interface that can be captured on
+#[derive(Clone)]
+pub struct Interface {
+    pub name: String,
+    pub description: String,
+}
+
+#[derive(Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum Game {
     Hsr,
     Gi,
@@ -19,56 +80,86 @@ pub enum Game {
 }
 
 impl Game {
-    pub fn achievements(self, message_tx: &mpsc::Sender<Message>) {
-        let message_tx = message_tx.clone();
-
-        thread::spawn(move || {
-            let achievement_ids = match self.achievement_ids() {
-                Ok(achievement_ids) => achievement_ids,
-                Err(e) => {
-                    message_tx
-                        .send(Message::GoTo(State::Error(e.to_string())))
-                        .unwrap();
-                    return;
-                }
-            };
-
-            let devices = match self.devices() {
-                Ok(devices) => devices,
-                Err(e) => {
-                    message_tx
-                        .send(Message::GoTo(State::Error(e.to_string())))
-                        .unwrap();
-                    return;
+    pub fn achievements(
+        self,
+        stardb: &stardb::Client,
+        interfaces: &[String],
+        message_tx: &mpsc::Sender<Message>,
+    ) -> u64 {
+        let stardb = stardb.clone();
+        let interfaces = interfaces.to_vec();
+        let capture_tx = message_tx.clone();
+
+        jobs::spawn(
+            message_tx,
+            t!("capture.job", game = self.name()),
+            move |context| {
+                context.progress(t!("capture.loading"), None);
+
+                let metadata = stardb.achievements(self)?;
+                let achievement_ids: Vec<_> = metadata.iter().map(|a| a.id).collect();
+
+                let devices = devices(&interfaces)?;
+
+                // Set once the capture is over, whether it was cancelled or not
+                let stop = Arc::new(AtomicBool::new(false));
+
+                let (device_tx, device_rx) = mpsc::channel();
+                for (i, device) in devices.into_iter().enumerate() {
+                    let device_tx = device_tx.clone();
+                    let message_tx = capture_tx.clone();
+                    let stop = stop.clone();
+                    let job = context.id();
+                    std::thread::spawn(move || {
+                        self.capture_device(i, device, &device_tx, &message_tx, &stop, job)
+                    });
                 }
-            };
 
-            let (device_tx, device_rx) = mpsc::channel();
-            for (i, device) in devices.into_iter().enumerate() {
-                let device_tx = device_tx.clone();
-                let message_tx = message_tx.clone();
-                std::thread::spawn(move || self.capture_device(i, device, &device_tx, &message_tx));
-            }
-
-            let achievements = match self {
-                Game::Hsr => hsr::sniff(&achievement_ids, &device_rx),
-                Game::Gi => gi::sniff(&achievement_ids, &device_rx),
-                _ => unimplemented!(),
-            };
-            let achievements = match achievements {
-                Ok(achievements) => achievements,
-                Err(e) => {
-                    message_tx
-                        .send(Message::GoTo(State::Error(e.to_string())))
-                        .unwrap();
-                    return;
+                context.progress(t!("capture.waiting"), None);
+
+                let achievements = match self {
+                    Game::Hsr => hsr::sniff(&achievement_ids, &device_rx, context),
+                    Game::Gi => gi::sniff(&achievement_ids, &device_rx, context),
+                    _ => unimplemented!(),
+                };
+                stop.store(true, Ordering::Relaxed);
+                let achievements = achievements?;
+
+                if let Err(e) = history::save(
+                    self,
+                    history::latest_uid(self),
+                    history::Export::Achievements(achievements.clone()),
+                ) {
+                    tracing::error!("Couldn't save history snapshot: {e}");
                 }
-            };
 
-            message_tx
-                .send(Message::GoTo(State::Achievements(achievements)))
-                .unwrap();
-        });
+                Ok(Achievements::new(achievements, metadata))
+            },
+            move |result| Message::Captured(self, result),
+        )
+    }
+
+    /// Opens already exported achievements in the achievement browser
+    pub fn browse_achievements(
+        self,
+        stardb: &stardb::Client,
+        achievements: Vec<u32>,
+        message_tx: &mpsc::Sender<Message>,
+    ) -> u64 {
+        let stardb = stardb.clone();
+
+        jobs::spawn(
+            message_tx,
+            t!("capture.loading_job", game = self.name()),
+            move |_| Ok(stardb.achievements(self)?),
+            |result| match result {
+                Ok(metadata) => Message::GoTo(State::Achievements(Achievements::new(
+                    achievements,
+                    metadata,
+                ))),
+                Err(e) => Message::GoTo(State::Error(e.to_string())),
+            },
+        )
     }
 
     pub fn game_path(self) -> anyhow::Result<PathBuf> {
@@ -79,6 +170,31 @@ impl Game {
         }
     }
 
+    pub fn id(self) -> &'static str {
+        match self {
+            Game::Hsr => "hsr",
+            Game::Gi => "gi",
+            Game::Zzz => "zzz",
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            Game::Hsr => t!("game.hsr"),
+            Game::Gi => t!("game.gi"),
+            Game::Zzz => t!("game.zzz"),
+        }
+    }
+
+    /// Key of the stardb import json
+    pub fn achievements_key(self) -> &'static str {
+        match self {
+            Game::Hsr => "hsr_achievements",
+            Game::Gi => "gi_achievements",
+            _ => unimplemented!(),
+        }
+    }
+
     pub fn achievement_url(self) -> String {
         let prefix = match self {
             Game::Hsr => "",
@@ -99,39 +215,14 @@ impl Game {
         format!("https://stardb.gg/{path}")
     }
 
-    fn achievement_ids(self) -> anyhow::Result<Vec<u32>> {
-        #[derive(serde::Deserialize)]
-        struct Achievement {
-            id: u32,
-        }
-
-        let url = match self {
-            Game::Hsr => "https://stardb.gg/api/achievements",
-            Game::Gi => "https://stardb.gg/api/gi/achievements",
-            _ => unimplemented!(),
-        };
-
-        let achievements: Vec<Achievement> = ureq::get(url).call()?.body_mut().read_json()?;
-        let achievement_ids: Vec<_> = achievements.into_iter().map(|a| a.id).collect();
-
-        Ok(achievement_ids)
-    }
-
-    fn devices(self) -> anyhow::Result<Vec<pcap::Device>> {
-        Ok(pcap::Device::list()?
-            .into_iter()
-            .filter(|d| d.flags.connection_status == pcap::ConnectionStatus::Connected)
-            .filter(|d| !d.addresses.is_empty())
-            .filter(|d| !d.flags.is_loopback())
-            .collect())
-    }
-
     fn capture_device(
         self,
         i: usize,
         device: pcap::Device,
         device_tx: &mpsc::Sender<Vec<u8>>,
         message_tx: &mpsc::Sender<Message>,
+        stop: &AtomicBool,
+        job: u64,
     ) -> anyhow::Result<()> {
         let packet_filer = match self {
             Game::Hsr => "udp portrange 23301-23302",
@@ -141,93 +232,267 @@ impl Game {
 
         tracing::debug!("Finding devices...");
 
-        loop {
+        while !stop.load(Ordering::Relaxed) {
             let mut capture = pcap::Capture::from_device(device.clone())?
                 .immediate_mode(true)
                 .promisc(true)
-                .timeout(0)
+                .timeout(PACKET_POLL.as_millis() as i32)
                 .open()?;
 
             capture.filter(packet_filer, true)?;
 
             message_tx
                 .send(Message::Toast({
-                    let mut toast = egui_notify::Toast::success(format!("Device {i} Ready~!"));
+                    let mut toast = egui_notify::Toast::success(t!("capture.ready", device = i));
                     toast.duration(None);
                     toast
                 }))
                 .unwrap();
 
             message_tx
-                .send(Message::GoTo(State::Waiting("Running".to_string())))
+                .send(Message::Wait(t!("common.running"), job))
                 .unwrap();
             tracing::info!("Device {i} Ready~!");
 
             let mut has_captured = false;
 
             loop {
+                if stop.load(Ordering::Relaxed) {
+                    return Ok(());
+                }
+
                 match capture.next_packet() {
                     Ok(packet) => {
                         device_tx.send(packet.data.to_vec())?;
                         has_captured = true;
+
+                        let pcap_stats = capture.stats().ok();
+                        record_capture(&device.name, |stats| {
+                            stats.packets += 1;
+
+                            if let Some(pcap_stats) = pcap_stats {
+                                stats.dropped = pcap_stats.dropped;
+                                stats.if_dropped = pcap_stats.if_dropped;
+                            }
+                        });
                     }
-                    Err(_) if !has_captured => break,
                     Err(pcap::Error::TimeoutExpired) => continue,
+                    Err(_) if !has_captured => break,
                     Err(e) => return Err(anyhow::anyhow!("{e}")),
                 }
             }
 
             message_tx
                 .send(Message::Toast({
-                    let mut toast = egui_notify::Toast::error(format!(
-                        "Device {i} Error. Starting up again..."
-                    ));
+                    let mut toast = egui_notify::Toast::error(t!("capture.restarting", device = i));
                     toast.duration(None);
                     toast
                 }))
                 .unwrap();
             tracing::info!("Device {i} Error. Starting up again...");
+            record_capture(&device.name, |stats| stats.restarts += 1);
+        }
+
+        Ok(())
+    }
+}
+
+fn connected_devices() -> anyhow::Result<Vec<pcap::Device>> {
+    Ok(pcap::Device::list()?
+        .into_iter()
+        .filter(|d| d.flags.connection_status == pcap::ConnectionStatus::Connected)
+        .filter(|d| !d.addresses.is_empty())
+        .filter(|d| !d.flags.is_loopback())
+        .collect())
+}
+
+/// The devices to capture on, all connected ones if `interfaces` is empty
+fn devices(interfaces: &[String]) -> anyhow::Result<Vec<pcap::Device>> {
+    let devices: Vec<_> = connected_devices()?
+        .into_iter()
+        .filter(|d| interfaces.is_empty() || interfaces.contains(&d.name))
+        .collect();
+
+    if devices.is_empty() && !interfaces.is_empty() {
+        return Err(anyhow::anyhow!(
+            "None of the selected capture interfaces are connected"
+        ));
+    }
+
+    Ok(devices)
+}
+
+pub fn interfaces() -> anyhow::Result<Vec<Interface>> {
+    Ok(connected_devices()?
+        .into_iter()
+        .map(|d| Interface {
+            description: d.desc.clone().unwrap_or(d.name.clone()),
+            name: d.name,
+        })
+        .collect())
+}
+
+/// The next captured packet, `None` once all devices stopped
+fn next_packet(
+    device_rx: &mpsc::Receiver<Vec<u8>>,
+    context: &jobs::Context,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    loop {
+        match device_rx.recv_timeout(PACKET_POLL) {
+            Ok(data) => return Ok(Some(data)),
+            Err(mpsc::RecvTimeoutError::Timeout) => context.check()?,
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(None),
+        }
+    }
+}
+
+/// Looks for the first of `logs`, relative to a `LocalLow` dir, that exists natively or in a
+/// Wine prefix and returns the first path `find` picks out of it
+fn game_path_from_logs(
+    logs: &[&str],
+    find: impl Fn(&str) -> Option<&str>,
+) -> anyhow::Result<PathBuf> {
+    let Some((local_low, log_path)) = wine::local_low_dirs().into_iter().find_map(|l| {
+        let log_path = logs
+            .iter()
+            .map(|log| l.dir.join(log))
+            .find(|p| p.exists())?;
+        Some((l, log_path))
+    }) else {
+        return Err(anyhow::anyhow!("Can't find log file"));
+    };
+
+    for line in BufReader::new(File::open(log_path)?).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+
+        if let Some(path) = find(&line) {
+            return Ok(local_low.host_path(path));
         }
     }
+
+    Err(anyhow::anyhow!("Couldn't find game path"))
 }
 
-pub fn pulls_from_game_path(path: &Path) -> anyhow::Result<String> {
+/// Returns the gacha log urls found in the web caches of all versions, newest first
+fn pull_url_candidates(path: &Path) -> anyhow::Result<Vec<cache::Entry>> {
     let mut path = path.to_path_buf();
 
     path.push("webCaches");
 
     let re = Regex::new(r"^\d+\.\d+\.\d+\.\d+$")?;
-    let mut paths: Vec<_> = path
+    let cache_dirs = path
         .read_dir()?
         .flat_map(|r| r.ok().map(|d| d.path()))
         .filter(|p| re.is_match(p.file_name().and_then(|o| o.to_str()).unwrap_or_default()))
-        .collect();
-    paths.sort();
-
-    let mut cache_path = paths[paths.len() - 1].clone();
-    cache_path.push("Cache");
-    cache_path.push("Cache_Data");
-    cache_path.push("data_2");
-
-    let bytes = std::fs::read(cache_path)?;
-    let data = String::from_utf8_lossy(&bytes);
-    let lines: Vec<_> = data.split("1/0/").collect();
-
-    for line in lines.iter().rev() {
-        if line.starts_with("https://") && line.contains("getGachaLog") {
-            if let Some(url) = line.split('\0').next() {
-                if ureq::get(url)
-                    .call()
-                    .ok()
-                    .and_then(|mut r| r.body_mut().read_json::<serde_json::Value>().ok())
-                    .map(|j| j["retcode"] == 0)
-                    .unwrap_or_default()
-                {
-                    return Ok(url.to_string());
-                }
+        .map(|p| p.join("Cache").join("Cache_Data"));
+
+    let mut candidates: Vec<cache::Entry> = Vec::new();
+
+    for dir in cache_dirs {
+        let entries = match cache::entries(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                tracing::warn!("Skipping web cache {}: {e}", dir.display());
+                continue;
             }
+        };
+
+        candidates.extend(
+            entries
+                .into_iter()
+                .filter(|e| e.url.contains("getGachaLog")),
+        );
+    }
+
+    candidates.sort_by_key(|c| std::cmp::Reverse(c.timestamp));
+    let mut seen = std::collections::HashSet::new();
+    candidates.retain(|c| seen.insert(c.url.clone()));
+
+    Ok(candidates)
+}
+
+/// Candidates validated at the same time
+const VALIDATION_THREADS: usize = 8;
+
+/// A gacha log url from the web cache and what the api said about it
+#[derive(Clone)]
+pub struct PullUrl {
+    pub url: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub uid: Option<String>,
+    /// Detected from the url, e.g. "Global, Europe"
+    pub region: String,
+    /// Why the api rejected the url, `None` if it's valid
+    pub error: Option<String>,
+}
+
+/// Validates all gacha log urls of the web caches in parallel, newest first
+pub fn pull_urls(
+    game: Game,
+    path: &Path,
+    agent: &ureq::Agent,
+    context: &jobs::Context,
+) -> anyhow::Result<Vec<PullUrl>> {
+    let candidates = pull_url_candidates(path)?;
+
+    if candidates.is_empty() {
+        return Err(anyhow::anyhow!("Couldn't find pull url"));
+    }
+
+    let mut pull_urls = Vec::new();
+
+    for (i, chunk) in candidates.chunks(VALIDATION_THREADS).enumerate() {
+        context.check()?;
+        context.progress(
+            t!("pull_menu.validating"),
+            Some((i * VALIDATION_THREADS) as f32 / candidates.len() as f32),
+        );
+
+        thread::scope(|s| {
+            let handles: Vec<_> = chunk
+                .iter()
+                .map(|c| s.spawn(move || validate(agent, game, c)))
+                .collect();
+
+            pull_urls.extend(handles.into_iter().map(|h| h.join().unwrap()));
+        });
+    }
+
+    Ok(pull_urls)
+}
+
+fn validate(agent: &ureq::Agent, game: Game, candidate: &cache::Entry) -> PullUrl {
+    let mut pull_url = PullUrl {
+        url: candidate.url.clone(),
+        timestamp: candidate.timestamp,
+        uid: None,
+        region: gacha::UrlInfo::parse(&candidate.url).name(),
+        error: None,
+    };
+
+    let json = agent
+        .get(gacha::api_url(game, &candidate.url))
+        .call()
+        .map_err(anyhow::Error::from)
+        .and_then(|mut r| Ok(r.body_mut().read_json::<serde_json::Value>()?));
+
+    let json = match json {
+        Ok(json) => json,
+        Err(e) => {
+            pull_url.error = Some(e.to_string());
+            return pull_url;
         }
+    };
+
+    if json["retcode"] != 0 {
+        pull_url.error = Some(json["message"].as_str().unwrap_or("Invalid").to_string());
+        return pull_url;
     }
 
-    Err(anyhow::anyhow!("Couldn't find pull url"))
+    pull_url.uid = json["data"]["list"][0]["uid"]
+        .as_str()
+        .map(|uid| uid.to_string());
+    pull_url
 }
@@ -0,0 +1,161 @@
+//! Discovery of Wine, Proton, Lutris and Bottles prefixes the games might run in
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// A `LocalLow` directory the games write their logs to
+pub struct LocalLow {
+    pub dir: PathBuf,
+    /// The prefix `dir` lives in, `None` when running natively on Windows
+    prefix: Option<PathBuf>,
+}
+
+impl LocalLow {
+    /// Translates a Windows path found in a log to a path on this machine
+    pub fn host_path(&self, path: &str) -> PathBuf {
+        let Some(prefix) = &self.prefix else {
+            return PathBuf::from(path);
+        };
+
+        let (drive, rest) = path.split_once(':').unwrap_or(("c", path));
+        let drive = drive.to_lowercase();
+
+        let mut host_path = prefix.join("dosdevices").join(format!("{drive}:"));
+        if !host_path.exists() && drive == "c" {
+            host_path = prefix.join("drive_c");
+        }
+
+        for component in rest.split(['\\', '/']).filter(|c| !c.is_empty()) {
+            host_path.push(component);
+        }
+
+        host_path
+    }
+}
+
+pub fn local_low_dirs() -> Vec<LocalLow> {
+    let mut dirs = Vec::new();
+
+    if let Ok(app_data) = std::env::var("APPDATA") {
+        let mut dir = PathBuf::from(app_data);
+        dir.pop();
+        dir.push("LocalLow");
+
+        dirs.push(LocalLow { dir, prefix: None });
+    }
+
+    if cfg!(windows) {
+        return dirs;
+    }
+
+    for prefix in prefixes() {
+        let Ok(users) = prefix.join("drive_c").join("users").read_dir() else {
+            continue;
+        };
+
+        for user in users.flatten() {
+            let dir = user.path().join("AppData").join("LocalLow");
+
+            if dir.exists() {
+                dirs.push(LocalLow {
+                    dir,
+                    prefix: Some(prefix.clone()),
+                });
+            }
+        }
+    }
+
+    dirs
+}
+
+/// All prefixes with a `drive_c`, custom `WINEPREFIX` first
+fn prefixes() -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+
+    if let Ok(prefix) = std::env::var("WINEPREFIX") {
+        candidates.push(PathBuf::from(prefix));
+    }
+
+    let Ok(home) = std::env::var("HOME").map(PathBuf::from) else {
+        return candidates;
+    };
+
+    candidates.push(home.join(".wine"));
+
+    for library in steam_libraries(&home) {
+        candidates
+            .extend(subdirs(&library.join("steamapps").join("compatdata")).map(|p| p.join("pfx")));
+    }
+
+    candidates.extend(lutris_prefixes(&home));
+    candidates.extend(subdirs(&home.join("Games")));
+
+    candidates.extend(subdirs(&home.join(".local/share/bottles/bottles")));
+    candidates.extend(subdirs(
+        &home.join(".var/app/com.usebottles.bottles/data/bottles/bottles"),
+    ));
+
+    let mut prefixes: Vec<PathBuf> = Vec::new();
+    for candidate in candidates {
+        if candidate.join("drive_c").is_dir() && !prefixes.contains(&candidate) {
+            prefixes.push(candidate);
+        }
+    }
+
+    prefixes
+}
+
+/// Steam roots and the extra library folders listed in their `libraryfolders.vdf`
+fn steam_libraries(home: &Path) -> Vec<PathBuf> {
+    let roots = [
+        home.join(".steam/steam"),
+        home.join(".local/share/Steam"),
+        home.join(".var/app/com.valvesoftware.Steam/.local/share/Steam"),
+    ];
+
+    let mut libraries = Vec::new();
+
+    for root in roots {
+        let vdf = fs::read_to_string(root.join("steamapps").join("libraryfolders.vdf"))
+            .unwrap_or_default();
+
+        libraries.extend(vdf.lines().filter_map(|line| {
+            let mut parts = line.split('"').filter(|p| !p.trim().is_empty());
+
+            match (parts.next(), parts.next()) {
+                (Some("path"), Some(path)) => Some(PathBuf::from(path)),
+                _ => None,
+            }
+        }));
+        libraries.push(root);
+    }
+
+    libraries
+}
+
+/// Prefixes from the `prefix:` lines of the Lutris game configs
+fn lutris_prefixes(home: &Path) -> Vec<PathBuf> {
+    entries(&home.join(".config/lutris/games"))
+        .filter_map(|p| fs::read_to_string(p).ok())
+        .flat_map(|config| {
+            config
+                .lines()
+                .filter_map(|l| l.trim().strip_prefix("prefix:"))
+                .map(|p| PathBuf::from(p.trim().trim_matches(['"', '\''])))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn subdirs(dir: &Path) -> impl Iterator<Item = PathBuf> {
+    entries(dir).filter(|p| p.is_dir())
+}
+
+fn entries(dir: &Path) -> impl Iterator<Item = PathBuf> {
+    dir.read_dir()
+        .into_iter()
+        .flatten()
+        .flat_map(|r| r.ok().map(|d| d.path()))
+}
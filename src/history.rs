@@ -0,0 +1,123 @@
+use std::{collections::HashSet, fs};
+
+use crate::{
+    games::{Game, PullUrl},
+    i18n::t,
+};
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub enum Export {
+    Achievements(Vec<u32>),
+    Pulls(String),
+}
+
+/// A successful export, stored as `history/<timestamp>.json` in the storage dir
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct Snapshot {
+    pub game: Game,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub uid: Option<String>,
+    pub export: Export,
+}
+
+impl Export {
+    pub fn name(&self) -> String {
+        match self {
+            Export::Achievements(_) => t!("history.achievements"),
+            Export::Pulls(_) => t!("history.pull_url"),
+        }
+    }
+}
+
+pub fn save(game: Game, uid: Option<String>, export: Export) -> anyhow::Result<()> {
+    let mut snapshot = Snapshot {
+        game,
+        timestamp: chrono::Utc::now(),
+        uid,
+        export,
+    };
+
+    let dir = crate::storage_dir("history")?;
+    let mut path = dir.join(format!("{}.json", snapshot.timestamp.timestamp_millis()));
+
+    // Several urls can be saved within the same millisecond
+    while path.exists() {
+        snapshot.timestamp += chrono::Duration::milliseconds(1);
+        path = dir.join(format!("{}.json", snapshot.timestamp.timestamp_millis()));
+    }
+
+    fs::write(path, serde_json::to_vec(&snapshot)?)?;
+
+    Ok(())
+}
+
+/// Saves the newest valid url of each uid unless it's already in the history
+pub fn save_pull_urls(game: Game, pull_urls: &[PullUrl]) -> anyhow::Result<()> {
+    let snapshots = load()?;
+
+    let mut valid: Vec<_> = pull_urls.iter().filter(|p| p.error.is_none()).collect();
+    valid.sort_by_key(|p| std::cmp::Reverse(p.timestamp));
+
+    let mut uids = HashSet::new();
+    for pull_url in valid {
+        if !uids.insert(&pull_url.uid) {
+            continue;
+        }
+
+        let saved = snapshots.iter().any(|s| {
+            s.game == game && matches!(&s.export, Export::Pulls(url) if *url == pull_url.url)
+        });
+        if !saved {
+            save(
+                game,
+                pull_url.uid.clone(),
+                Export::Pulls(pull_url.url.clone()),
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The uid of the newest pull url of `game`, the account the game was last logged into
+pub fn latest_uid(game: Game) -> Option<String> {
+    load()
+        .ok()?
+        .into_iter()
+        .filter(|s| s.game == game && matches!(s.export, Export::Pulls(_)))
+        .find_map(|s| s.uid)
+}
+
+/// Loads all snapshots, newest first
+pub fn load() -> anyhow::Result<Vec<Snapshot>> {
+    let mut snapshots: Vec<Snapshot> = crate::storage_dir("history")?
+        .read_dir()?
+        .flat_map(|r| r.ok().map(|d| d.path()))
+        .filter_map(|p| {
+            let snapshot = fs::read(&p)
+                .map_err(anyhow::Error::from)
+                .and_then(|b| Ok(serde_json::from_slice(&b)?));
+
+            match snapshot {
+                Ok(snapshot) => Some(snapshot),
+                Err(e) => {
+                    tracing::warn!("Skipping history snapshot {}: {e}", p.display());
+                    None
+                }
+            }
+        })
+        .collect();
+
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.timestamp));
+
+    Ok(snapshots)
+}
+
+pub fn delete(snapshot: &Snapshot) -> anyhow::Result<()> {
+    let mut path = crate::storage_dir("history")?;
+    path.push(format!("{}.json", snapshot.timestamp.timestamp_millis()));
+
+    fs::remove_file(path)?;
+
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+#[derive(Default, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum ProxyMode {
+    /// Honours `HTTPS_PROXY`, `ALL_PROXY` and `NO_PROXY`
+    #[default]
+    Environment,
+    Direct,
+    Custom,
+}
+
+/// Proxy for all outbound requests, including the updater
+#[derive(Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Proxy {
+    pub mode: ProxyMode,
+    /// E.g. `http://127.0.0.1:8080` or `socks5://127.0.0.1:1080`
+    pub url: String,
+    pub username: String,
+    pub password: String,
+}
+
+impl Proxy {
+    fn ureq(&self) -> anyhow::Result<Option<ureq::Proxy>> {
+        match self.mode {
+            ProxyMode::Environment => Ok(ureq::Proxy::try_from_env()),
+            ProxyMode::Direct => Ok(None),
+            ProxyMode::Custom => {
+                let proxy = ureq::Proxy::new(self.url.trim())?;
+
+                if self.username.is_empty() {
+                    return Ok(Some(proxy));
+                }
+
+                // The builder puts them into a url as they are, so `@`, `:` or `/` would end
+                // up in the host
+                let proxy = ureq::Proxy::builder(proxy.protocol())
+                    .host(proxy.host())
+                    .port(proxy.port())
+                    .username(&percent_encode(&self.username))
+                    .password(&percent_encode(&self.password))
+                    .build()?;
+
+                Ok(Some(proxy))
+            }
+        }
+    }
+}
+
+/// The agent every outbound request goes through
+pub fn agent(proxy: &Proxy, timeout: Duration) -> anyhow::Result<ureq::Agent> {
+    Ok(ureq::Agent::config_builder()
+        .timeout_global(Some(timeout))
+        .http_status_as_error(false)
+        .proxy(proxy.ureq()?)
+        .build()
+        .into())
+}
+
+/// Encodes everything but the unreserved characters of RFC 3986
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            b => format!("%{b:02X}"),
+        })
+        .collect()
+}
@@ -0,0 +1,164 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{LazyLock, RwLock},
+};
+
+/// Looks up a translated string, with optional `{name}` arguments
+///
+/// ```ignore
+/// t!("menu.history")
+/// t!("export.saved_to", path = path.display())
+/// ```
+macro_rules! t {
+    ($key:expr) => {
+        $crate::i18n::translate($key, None, &[])
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::translate(
+            $key,
+            None,
+            &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+],
+        )
+    };
+}
+
+/// Like `t!`, but picks the plural form for `n`, which is also available as `{n}`
+macro_rules! tn {
+    ($key:expr, $n:expr) => {
+        $crate::i18n::translate($key, Some($n), &[])
+    };
+    ($key:expr, $n:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::translate(
+            $key,
+            Some($n),
+            &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+],
+        )
+    };
+}
+
+pub(crate) use {t, tn};
+
+/// Used for keys that are missing in the current language
+const FALLBACK: Language = Language::English;
+
+static LANGUAGE: RwLock<Language> = RwLock::new(FALLBACK);
+
+static CATALOGS: LazyLock<HashMap<Language, Catalog>> = LazyLock::new(|| {
+    Language::ALL
+        .into_iter()
+        .filter_map(|language| match serde_json::from_str(language.catalog()) {
+            Ok(catalog) => Some((language, catalog)),
+            Err(e) => {
+                tracing::error!("Invalid {} catalog: {e}", language.code());
+                None
+            }
+        })
+        .collect()
+});
+
+#[derive(
+    Default, Clone, Copy, PartialEq, Eq, Hash, Debug, serde::Serialize, serde::Deserialize,
+)]
+pub enum Language {
+    #[default]
+    English,
+    SimplifiedChinese,
+    Japanese,
+}
+
+type Catalog = HashMap<String, Entry>;
+
+/// A catalog entry, either a plain string or one string per plural category
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum Entry {
+    Text(String),
+    Plural(HashMap<String, String>),
+}
+
+impl Language {
+    pub const ALL: [Language; 3] = [
+        Language::English,
+        Language::SimplifiedChinese,
+        Language::Japanese,
+    ];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::SimplifiedChinese => "zh-CN",
+            Language::Japanese => "ja",
+        }
+    }
+
+    /// The name of the language in itself
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::SimplifiedChinese => "简体中文",
+            Language::Japanese => "日本語",
+        }
+    }
+
+    fn catalog(self) -> &'static str {
+        match self {
+            Language::English => include_str!("../locales/en.json"),
+            Language::SimplifiedChinese => include_str!("../locales/zh-CN.json"),
+            Language::Japanese => include_str!("../locales/ja.json"),
+        }
+    }
+
+    /// CLDR plural category of `n`
+    fn plural(self, n: usize) -> &'static str {
+        match self {
+            Language::English if n == 1 => "one",
+            _ => "other",
+        }
+    }
+}
+
+pub fn set_language(language: Language) {
+    *LANGUAGE.write().unwrap() = language;
+}
+
+pub fn language() -> Language {
+    *LANGUAGE.read().unwrap()
+}
+
+/// Use `t!` and `tn!` instead
+pub fn translate(key: &str, n: Option<usize>, args: &[(&str, &dyn fmt::Display)]) -> String {
+    let language = language();
+
+    let mut text = match lookup(language, key, n).or_else(|| lookup(FALLBACK, key, n)) {
+        Some(text) => text.to_string(),
+        None => {
+            tracing::warn!("Missing translation for {key}");
+            key.to_string()
+        }
+    };
+
+    if let Some(n) = n {
+        text = text.replace("{n}", &n.to_string());
+    }
+
+    for (name, value) in args {
+        text = text.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+
+    text
+}
+
+fn lookup(language: Language, key: &str, n: Option<usize>) -> Option<&'static str> {
+    match CATALOGS.get(&language)?.get(key)? {
+        Entry::Text(text) => Some(text),
+        Entry::Plural(forms) => {
+            let category = language.plural(n.unwrap_or(0));
+
+            forms
+                .get(category)
+                .or_else(|| forms.get("other"))
+                .map(|s| s.as_str())
+        }
+    }
+}
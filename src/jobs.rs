@@ -0,0 +1,137 @@
+use std::{
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+use crate::app::Message;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A running job as shown in the job list
+#[derive(Clone)]
+pub struct Job {
+    pub id: u64,
+    pub name: String,
+    pub progress: String,
+    /// Between 0 and 1, `None` if the job can't tell how far it is
+    pub fraction: Option<f32>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Job {
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+}
+
+pub enum Event {
+    Started(Job),
+    Progress(u64, String, Option<f32>),
+    Finished { id: u64, cancelled: bool },
+}
+
+/// Returned by jobs that stopped because they were cancelled
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Handed to a job to report progress and notice cancellation
+pub struct Context {
+    id: u64,
+    cancel: Arc<AtomicBool>,
+    message_tx: mpsc::Sender<Message>,
+}
+
+impl Context {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn progress(&self, progress: impl Into<String>, fraction: Option<f32>) {
+        let _ = self.message_tx.send(Message::Job(Event::Progress(
+            self.id,
+            progress.into(),
+            fraction,
+        )));
+    }
+
+    /// Fails with `Cancelled` once the job was cancelled. Long running jobs call this
+    /// between steps
+    pub fn check(&self) -> anyhow::Result<()> {
+        if self.cancel.load(Ordering::Relaxed) {
+            return Err(Cancelled.into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs `job` on its own thread and sends the message `done` makes out of its result.
+/// `done` isn't called for cancelled jobs, even if they ran to the end. Returns the id of
+/// the job
+pub fn spawn<T: Send + 'static>(
+    message_tx: &mpsc::Sender<Message>,
+    name: impl Into<String>,
+    job: impl FnOnce(&Context) -> anyhow::Result<T> + Send + 'static,
+    done: impl FnOnce(anyhow::Result<T>) -> Message + Send + 'static,
+) -> u64 {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let cancel = Arc::new(AtomicBool::new(false));
+    let name = name.into();
+
+    tracing::info!("Starting job {id}: {name}");
+
+    message_tx
+        .send(Message::Job(Event::Started(Job {
+            id,
+            name,
+            progress: String::new(),
+            fraction: None,
+            cancel: cancel.clone(),
+        })))
+        .unwrap();
+
+    let context = Context {
+        id,
+        cancel,
+        message_tx: message_tx.clone(),
+    };
+
+    thread::spawn(move || {
+        let result = job(&context);
+
+        // Most jobs can't stop halfway, their result is dropped instead
+        let cancelled = context.cancel.load(Ordering::Relaxed)
+            || matches!(&result, Err(e) if e.is::<Cancelled>());
+
+        if let Err(e) = &result {
+            tracing::warn!("Job {id} failed: {e}");
+        }
+
+        let _ = context
+            .message_tx
+            .send(Message::Job(Event::Finished { id, cancelled }));
+
+        if !cancelled {
+            let _ = context.message_tx.send(done(result));
+        }
+    });
+
+    id
+}
@@ -0,0 +1,71 @@
+use std::{fs, path::PathBuf};
+
+use crate::config::LogLevel;
+
+/// Lines kept by the log viewer
+const MAX_LINES: usize = 10_000;
+
+#[derive(Clone)]
+pub struct Line {
+    pub level: LogLevel,
+    pub text: String,
+}
+
+pub fn dir() -> anyhow::Result<PathBuf> {
+    crate::storage_dir("log")
+}
+
+/// Daily log files, newest first
+pub fn files() -> anyhow::Result<Vec<PathBuf>> {
+    let mut files: Vec<_> = fs::read_dir(dir()?)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect();
+
+    // Named log.YYYY-MM-DD
+    files.sort();
+    files.reverse();
+
+    Ok(files)
+}
+
+/// The lines of the last `days` log files, oldest first
+pub fn recent(days: usize) -> anyhow::Result<Vec<Line>> {
+    let mut lines = Vec::new();
+
+    for file in files()?.into_iter().take(days).rev() {
+        let text = fs::read_to_string(file)?;
+
+        let mut level = LogLevel::Info;
+        for text in text.lines() {
+            // Continuation lines of a multi line message keep its level
+            if let Some(l) = parse_level(text) {
+                level = l;
+            }
+
+            lines.push(Line {
+                level,
+                text: text.to_string(),
+            });
+        }
+    }
+
+    if lines.len() > MAX_LINES {
+        lines.drain(..lines.len() - MAX_LINES);
+    }
+
+    Ok(lines)
+}
+
+/// Lines look like `2025-01-01T12:00:00.000000Z  INFO stardb_exporter::app: message`
+fn parse_level(line: &str) -> Option<LogLevel> {
+    match line.split_whitespace().nth(1)? {
+        "ERROR" => Some(LogLevel::Error),
+        "WARN" => Some(LogLevel::Warn),
+        "INFO" => Some(LogLevel::Info),
+        "DEBUG" => Some(LogLevel::Debug),
+        "TRACE" => Some(LogLevel::Trace),
+        _ => None,
+    }
+}
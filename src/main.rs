@@ -1,12 +1,39 @@
 #![windows_subsystem = "windows"]
 
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
 mod app;
+mod archive;
+mod backup;
+mod companion;
+mod config;
+mod credentials;
+mod diagnostics;
+mod export;
+mod fonts;
+mod gacha;
 mod games;
+mod history;
+mod http;
+mod i18n;
+mod jobs;
+mod logs;
+mod stardb;
+mod stats;
+mod sync;
 mod themes;
 mod ui;
+mod updater;
 
 const APP_ID: &str = "Stardb Exporter";
 
+type LogFilter = tracing_subscriber::reload::Handle<
+    tracing_subscriber::filter::LevelFilter,
+    tracing_subscriber::Registry,
+>;
+
+static LOG_FILTER: std::sync::OnceLock<LogFilter> = std::sync::OnceLock::new();
+
 fn main() -> anyhow::Result<()> {
     let _guard = tracing_init()?;
 
@@ -30,19 +57,40 @@ fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-fn tracing_init() -> anyhow::Result<tracing_appender::non_blocking::WorkerGuard> {
+/// Returns the directory `name` inside the app storage dir and creates it if needed
+fn storage_dir(name: &str) -> anyhow::Result<std::path::PathBuf> {
     let mut storage_dir =
         anyhow::Context::context(eframe::storage_dir(APP_ID), "Storage dir not found")?;
-    storage_dir.push("log");
+    storage_dir.push(name);
+
+    std::fs::create_dir_all(&storage_dir)?;
 
-    let appender = tracing_appender::rolling::daily(storage_dir, "log");
+    Ok(storage_dir)
+}
+
+fn tracing_init() -> anyhow::Result<tracing_appender::non_blocking::WorkerGuard> {
+    let appender = tracing_appender::rolling::daily(storage_dir("log")?, "log");
     let (non_blocking_appender, guard) = tracing_appender::non_blocking(appender);
 
-    tracing_subscriber::fmt()
-        .with_writer(non_blocking_appender)
-        .with_ansi(false)
+    let (filter, handle) =
+        tracing_subscriber::reload::Layer::new(tracing_subscriber::filter::LevelFilter::INFO);
+    let _ = LOG_FILTER.set(handle);
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(non_blocking_appender)
+                .with_ansi(false),
+        )
         .init();
     tracing::info!("Tracing initialized and logging to file.");
 
     Ok(guard)
 }
+
+fn set_log_level(level: config::LogLevel) {
+    if let Some(Err(e)) = LOG_FILTER.get().map(|f| f.reload(level.filter())) {
+        tracing::error!("Couldn't change the log level: {e}");
+    }
+}
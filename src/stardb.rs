@@ -0,0 +1,260 @@
+use std::{fmt, thread, time::Duration};
+
+use crate::games::Game;
+
+pub const DEFAULT_URL: &str = "https://stardb.gg/api";
+
+const ATTEMPTS: u32 = 3;
+const BACKOFF: Duration = Duration::from_millis(500);
+
+#[derive(Debug)]
+pub enum Error {
+    /// The session cookie is missing, expired or was revoked
+    AuthExpired,
+    Status(u16),
+    Decode(String),
+    Transport(ureq::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::AuthExpired => write!(f, "Your session expired. Try relogging"),
+            Error::Status(status) => write!(f, "stardb responded with status {status}"),
+            Error::Decode(e) => write!(f, "Couldn't read the stardb response: {e}"),
+            Error::Transport(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Error {
+    /// Whether the request might succeed when sent again
+    fn is_transient(&self) -> bool {
+        match self {
+            Error::Status(status) => *status == 429 || *status >= 500,
+            Error::Transport(_) => true,
+            _ => false,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Achievement metadata as returned by the stardb api
+#[derive(Clone, serde::Deserialize)]
+pub struct Achievement {
+    pub id: u32,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub series_name: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct PullImport {
+    pub uid: serde_json::Value,
+}
+
+#[derive(Clone)]
+pub struct Client {
+    base_url: String,
+    agent: ureq::Agent,
+}
+
+impl Client {
+    pub fn new(base_url: &str, agent: ureq::Agent) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            agent,
+        }
+    }
+
+    /// Returns the session cookie
+    pub fn login(&self, username: &str, password: &str) -> Result<String> {
+        let json = serde_json::json!({
+            "username": username,
+            "password": password
+        });
+
+        let response = self
+            .agent
+            .post(self.url("users/auth/login"))
+            .send_json(json)
+            .map_err(Error::Transport)?;
+
+        cookie(&check(response)?)
+    }
+
+    /// Returns the renewed session cookie and the username
+    pub fn renew(&self, cookie: &str) -> Result<(String, String)> {
+        let response = self
+            .agent
+            .post(self.url("users/auth/renew"))
+            .header("Cookie", cookie)
+            .send_empty()
+            .map_err(Error::Transport)?;
+
+        let mut response = check(response)?;
+
+        let cookie = self::cookie(&response)?;
+        let username = decode(&mut response)?;
+
+        Ok((cookie, username))
+    }
+
+    pub fn logout(&self, cookie: &str) -> Result<()> {
+        let response = self
+            .agent
+            .post(self.url("users/auth/logout"))
+            .header("Cookie", cookie)
+            .send_empty()
+            .map_err(Error::Transport)?;
+
+        check(response)?;
+
+        Ok(())
+    }
+
+    pub fn achievements(&self, game: Game) -> Result<Vec<Achievement>> {
+        let url = self.url(&format!("{}achievements", prefix(game)));
+
+        retry(|| {
+            let response = self.agent.get(&url).call().map_err(Error::Transport)?;
+
+            decode(&mut check(response)?)
+        })
+    }
+
+    pub fn completed(&self, game: Game, cookie: &str) -> Result<Vec<u32>> {
+        let url = self.completed_url(game);
+
+        retry(|| {
+            let response = self
+                .agent
+                .get(&url)
+                .header("Cookie", cookie)
+                .call()
+                .map_err(Error::Transport)?;
+
+            decode(&mut check(response)?)
+        })
+    }
+
+    pub fn add_completed(&self, game: Game, cookie: &str, achievements: &[u32]) -> Result<()> {
+        let url = self.completed_url(game);
+
+        retry(|| {
+            let response = self
+                .agent
+                .put(&url)
+                .header("Cookie", cookie)
+                .send_json(achievements)
+                .map_err(Error::Transport)?;
+
+            check(response).map(|_| ())
+        })
+    }
+
+    pub fn remove_completed(&self, game: Game, cookie: &str, achievements: &[u32]) -> Result<()> {
+        if achievements.is_empty() {
+            return Ok(());
+        }
+
+        let url = self.completed_url(game);
+
+        retry(|| {
+            let response = self
+                .agent
+                .delete(&url)
+                .header("Cookie", cookie)
+                .force_send_body()
+                .send_json(achievements)
+                .map_err(Error::Transport)?;
+
+            check(response).map(|_| ())
+        })
+    }
+
+    /// Imports the pulls behind a gacha log url. Works without an account as well
+    pub fn import_pulls(&self, game: Game, cookie: Option<&str>, url: &str) -> Result<PullImport> {
+        let path = match game {
+            Game::Hsr => "warps-import",
+            Game::Gi => "gi/wishes-import",
+            Game::Zzz => "zzz/signals-import",
+        };
+
+        let mut request = self.agent.post(self.url(path));
+        if let Some(cookie) = cookie {
+            request = request.header("Cookie", cookie);
+        }
+
+        let response = request
+            .send_json(serde_json::json!({ "url": url }))
+            .map_err(Error::Transport)?;
+
+        decode(&mut check(response)?)
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{path}", self.base_url)
+    }
+
+    fn completed_url(&self, game: Game) -> String {
+        self.url(&format!("users/me/{}achievements/completed", prefix(game)))
+    }
+}
+
+fn prefix(game: Game) -> &'static str {
+    match game {
+        Game::Hsr => "",
+        Game::Gi => "gi/",
+        Game::Zzz => "zzz/",
+    }
+}
+
+fn retry<T>(mut f: impl FnMut() -> Result<T>) -> Result<T> {
+    let mut backoff = BACKOFF;
+
+    for attempt in 1..ATTEMPTS {
+        match f() {
+            Err(e) if e.is_transient() => {
+                tracing::warn!("stardb request failed (attempt {attempt}/{ATTEMPTS}): {e}");
+
+                thread::sleep(backoff);
+                backoff *= 2;
+            }
+            result => return result,
+        }
+    }
+
+    f()
+}
+
+fn check(response: ureq::http::Response<ureq::Body>) -> Result<ureq::http::Response<ureq::Body>> {
+    match response.status().as_u16() {
+        200..=299 => Ok(response),
+        401 | 403 => Err(Error::AuthExpired),
+        status => Err(Error::Status(status)),
+    }
+}
+
+fn decode<T: serde::de::DeserializeOwned>(
+    response: &mut ureq::http::Response<ureq::Body>,
+) -> Result<T> {
+    response
+        .body_mut()
+        .read_json()
+        .map_err(|e| Error::Decode(e.to_string()))
+}
+
+fn cookie(response: &ureq::http::Response<ureq::Body>) -> Result<String> {
+    response
+        .headers()
+        .get("Set-Cookie")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|c| c.split(';').next())
+        .map(|c| c.to_string())
+        .ok_or(Error::AuthExpired)
+}
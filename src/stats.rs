@@ -0,0 +1,341 @@
+use crate::{
+    gacha::{History, Pull},
+    games::Game,
+    i18n::t,
+};
+
+/// Item ids of the five stars that can be lost to on limited character banners
+fn standard_five_stars(game: Game) -> &'static [&'static str] {
+    match game {
+        // Jean, Diluc, Qiqi, Mona, Keqing, Tighnari, Dehya, Yumemizuki Mizuki
+        Game::Gi => &[
+            "10000003", "10000016", "10000035", "10000041", "10000042", "10000069", "10000079",
+            "10000109",
+        ],
+        // Himeko, Welt, Bronya, Gepard, Clara, Yanqing, Bailu
+        Game::Hsr => &["1003", "1004", "1101", "1104", "1107", "1209", "1211"],
+        // Nekomata, Soldier 11, Koleda, Lycaon, Grace, Rina
+        Game::Zzz => &["1021", "1041", "1101", "1141", "1181", "1211"],
+    }
+}
+
+/// Genshin's gacha log leaves `item_id` empty, so its standard five stars are matched by name
+/// in the languages the app is translated to
+fn gi_standard_names(lang: &str) -> Option<&'static [&'static str]> {
+    let lang = lang.to_lowercase();
+
+    if lang.starts_with("en") {
+        Some(&[
+            "Jean",
+            "Diluc",
+            "Qiqi",
+            "Mona",
+            "Keqing",
+            "Tighnari",
+            "Dehya",
+            "Yumemizuki Mizuki",
+        ])
+    } else if lang.starts_with("zh-cn") {
+        Some(&[
+            "琴",
+            "迪卢克",
+            "七七",
+            "莫娜",
+            "刻晴",
+            "提纳里",
+            "迪希雅",
+            "梦见月瑞希",
+        ])
+    } else if lang.starts_with("ja") {
+        Some(&[
+            "ジン",
+            "ディルック",
+            "七七",
+            "モナ",
+            "刻晴",
+            "ティナリ",
+            "ディシア",
+            "夢見月瑞希",
+        ])
+    } else {
+        None
+    }
+}
+
+/// Whether the pull is a standard five star, `None` if the history doesn't tell
+fn is_standard(game: Game, lang: &str, pull: &Pull) -> Option<bool> {
+    if !pull.item_id.is_empty() {
+        return Some(standard_five_stars(game).contains(&pull.item_id.as_str()));
+    }
+
+    match game {
+        Game::Gi => gi_standard_names(lang).map(|names| names.contains(&pull.name.as_str())),
+        _ => None,
+    }
+}
+
+#[derive(Clone)]
+pub struct FiveStar {
+    pub name: String,
+    pub time: String,
+    pub pity: usize,
+    /// Whether the 50/50 was won. `None` for guarantees and banners without a 50/50
+    pub won: Option<bool>,
+}
+
+#[derive(Clone)]
+pub struct BannerStats {
+    pub name: String,
+    pub total: usize,
+    /// Pulls since the last five star
+    pub pity: usize,
+    /// Pulls since the last four star or better
+    pub four_star_pity: usize,
+    pub four_stars: usize,
+    /// Oldest first
+    pub five_stars: Vec<FiveStar>,
+    /// Five stars that were a 50/50 or a guarantee, but the history doesn't tell which
+    pub unknown_fifty_fifty: usize,
+}
+
+#[derive(Clone)]
+pub struct Stats {
+    pub game: Game,
+    pub uid: String,
+    pub banners: Vec<BannerStats>,
+}
+
+impl BannerStats {
+    pub fn average_pity(&self) -> Option<f32> {
+        if self.five_stars.is_empty() {
+            return None;
+        }
+
+        let sum: usize = self.five_stars.iter().map(|f| f.pity).sum();
+
+        Some(sum as f32 / self.five_stars.len() as f32)
+    }
+
+    /// Won 50/50s and the number of 50/50s
+    pub fn fifty_fifty(&self) -> Option<(usize, usize)> {
+        let attempts: Vec<bool> = self.five_stars.iter().filter_map(|f| f.won).collect();
+
+        if attempts.is_empty() {
+            return None;
+        }
+
+        Some((attempts.iter().filter(|w| **w).count(), attempts.len()))
+    }
+}
+
+impl Stats {
+    pub fn new(history: &History) -> Self {
+        let game = history.game;
+
+        let banners = game
+            .banners()
+            .iter()
+            .map(|(banner, name)| {
+                let mut pulls: Vec<&Pull> = history
+                    .pulls
+                    .iter()
+                    .filter(|p| p.banner(game) == *banner)
+                    .collect();
+
+                // Ids grow over time
+                pulls.sort_by_key(|p| (p.id.len(), p.id.as_str()));
+
+                banner_stats(game, &history.lang, banner, t!(*name), &pulls)
+            })
+            .collect();
+
+        Self {
+            game,
+            uid: history.uid.clone(),
+            banners,
+        }
+    }
+}
+
+fn banner_stats(
+    game: Game,
+    lang: &str,
+    banner: &str,
+    name: String,
+    pulls: &[&Pull],
+) -> BannerStats {
+    // Zzz ranks S, A and B as 4, 3 and 2
+    let (five_star, four_star) = match game {
+        Game::Zzz => ("4", "3"),
+        _ => ("5", "4"),
+    };
+
+    // The light cone and w-engine banners are 75/25 and aren't counted
+    let has_fifty_fifty = matches!(
+        (game, banner),
+        (Game::Gi, "301") | (Game::Hsr, "11") | (Game::Zzz, "2")
+    );
+
+    let mut stats = BannerStats {
+        name,
+        total: pulls.len(),
+        pity: 0,
+        four_star_pity: 0,
+        four_stars: 0,
+        five_stars: Vec::new(),
+        unknown_fifty_fifty: 0,
+    };
+
+    // `None` after a five star that couldn't be told apart
+    let mut guaranteed = Some(false);
+
+    for pull in pulls {
+        stats.pity += 1;
+        stats.four_star_pity += 1;
+
+        if pull.rank_type == four_star {
+            stats.four_stars += 1;
+            stats.four_star_pity = 0;
+        } else if pull.rank_type == five_star {
+            let standard = is_standard(game, lang, pull);
+
+            let won = if !has_fifty_fifty {
+                None
+            } else {
+                let (won, next) = match (guaranteed, standard) {
+                    (Some(true), _) => (None, Some(false)),
+                    (Some(false), Some(standard)) => (Some(!standard), Some(standard)),
+                    // A guarantee is never a standard five star
+                    (None, Some(true)) => (Some(false), Some(true)),
+                    (None, Some(false)) => (None, Some(false)),
+                    (_, None) => (None, None),
+                };
+
+                if won.is_none() && guaranteed != Some(true) {
+                    stats.unknown_fifty_fifty += 1;
+                }
+
+                guaranteed = next;
+                won
+            };
+
+            stats.five_stars.push(FiveStar {
+                name: pull.name.clone(),
+                time: pull.time.clone(),
+                pity: stats.pity,
+                won,
+            });
+
+            stats.pity = 0;
+            stats.four_star_pity = 0;
+        }
+    }
+
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pull(id: usize, gacha_type: &str, item_id: &str, name: &str, rank_type: &str) -> Pull {
+        Pull {
+            id: id.to_string(),
+            uid: "100000000".to_string(),
+            gacha_type: gacha_type.to_string(),
+            gacha_id: String::new(),
+            item_id: item_id.to_string(),
+            count: "1".to_string(),
+            time: "2024-01-01 00:00:00".to_string(),
+            name: name.to_string(),
+            item_type: String::new(),
+            rank_type: rank_type.to_string(),
+        }
+    }
+
+    fn stats(game: Game, lang: &str, banner: &str, pulls: &[Pull]) -> BannerStats {
+        let pulls: Vec<&Pull> = pulls.iter().collect();
+        banner_stats(game, lang, banner, String::new(), &pulls)
+    }
+
+    #[test]
+    fn standard_pools() {
+        assert_eq!(
+            standard_five_stars(Game::Gi),
+            [
+                "10000003", "10000016", "10000035", "10000041", "10000042", "10000069", "10000079",
+                "10000109"
+            ]
+        );
+        assert_eq!(
+            standard_five_stars(Game::Hsr),
+            ["1003", "1004", "1101", "1104", "1107", "1209", "1211"]
+        );
+        assert_eq!(
+            standard_five_stars(Game::Zzz),
+            ["1021", "1041", "1101", "1141", "1181", "1211"]
+        );
+
+        for lang in ["en-us", "zh-cn", "ja-jp"] {
+            assert_eq!(
+                gi_standard_names(lang).unwrap().len(),
+                standard_five_stars(Game::Gi).len()
+            );
+        }
+    }
+
+    #[test]
+    fn fifty_fifty_by_item_id() {
+        let pulls = [
+            // Lost to Koleda, then the guarantee
+            pull(1, "2001", "1101", "Koleda", "4"),
+            pull(2, "2001", "1191", "Ellen", "4"),
+            // Won
+            pull(3, "2001", "1191", "Ellen", "4"),
+        ];
+
+        let stats = stats(Game::Zzz, "en-us", "2", &pulls);
+        let won: Vec<_> = stats.five_stars.iter().map(|f| f.won).collect();
+
+        assert_eq!(won, [Some(false), None, Some(true)]);
+        assert_eq!(stats.fifty_fifty(), Some((1, 2)));
+        assert_eq!(stats.unknown_fifty_fifty, 0);
+    }
+
+    #[test]
+    fn gi_fifty_fifty_by_name() {
+        let pulls = [
+            pull(1, "301", "", "Diluc", "5"),
+            pull(2, "400", "", "Nahida", "5"),
+            pull(3, "301", "", "Furina", "5"),
+        ];
+
+        let stats = stats(Game::Gi, "en-us", "301", &pulls);
+
+        assert_eq!(stats.fifty_fifty(), Some((1, 2)));
+        assert_eq!(stats.unknown_fifty_fifty, 0);
+    }
+
+    #[test]
+    fn gi_unknown_language() {
+        let pulls = [
+            pull(1, "301", "", "Diluc", "5"),
+            pull(2, "301", "", "Furina", "5"),
+        ];
+
+        let stats = stats(Game::Gi, "fr-fr", "301", &pulls);
+
+        assert_eq!(stats.fifty_fifty(), None);
+        assert_eq!(stats.unknown_fifty_fifty, 2);
+    }
+
+    #[test]
+    fn no_fifty_fifty_on_75_25_banners() {
+        let pulls = [pull(1, "12", "23000", "Night on the Milky Way", "5")];
+
+        let stats = stats(Game::Hsr, "en-us", "12", &pulls);
+
+        assert_eq!(stats.fifty_fifty(), None);
+        assert_eq!(stats.unknown_fifty_fifty, 0);
+    }
+}
@@ -0,0 +1,144 @@
+use std::{fmt, sync::mpsc};
+
+use crate::{
+    app::{Message, SyncMode, User},
+    backup,
+    games::Game,
+    i18n::t,
+    jobs, stardb,
+};
+
+/// A request to stardb that can be sent again after relogging
+#[derive(Clone)]
+pub enum Sync {
+    Achievements {
+        game: Game,
+        sync_mode: SyncMode,
+        achievements: Vec<u32>,
+        /// Mirroring stops before it removes anything until it's confirmed
+        confirmed: bool,
+    },
+    Restore {
+        game: Game,
+        achievements: Vec<u32>,
+    },
+    Pulls {
+        game: Game,
+        url: String,
+    },
+}
+
+/// Returned by a mirror sync that would remove achievements and wasn't confirmed
+#[derive(Debug)]
+pub struct Unconfirmed(pub usize);
+
+impl fmt::Display for Unconfirmed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Mirroring would remove {} achievements", self.0)
+    }
+}
+
+impl std::error::Error for Unconfirmed {}
+
+impl Sync {
+    pub fn spawn(
+        self,
+        stardb: &stardb::Client,
+        user: Option<&User>,
+        message_tx: &mpsc::Sender<Message>,
+    ) {
+        let stardb = stardb.clone();
+        let user = user.cloned();
+        let job_user = user.clone();
+        let sync = self.clone();
+
+        jobs::spawn(
+            message_tx,
+            self.name(),
+            move |_| sync.run(&stardb, job_user.as_ref()),
+            move |result| match result {
+                Ok(s) => Message::Synced(s),
+                Err(e) => {
+                    if let Some(Unconfirmed(count)) = e.downcast_ref() {
+                        return Message::ConfirmMirror(self, *count);
+                    }
+
+                    match (e.downcast_ref(), user) {
+                        (Some(stardb::Error::AuthExpired), Some(user)) => {
+                            Message::Expired(user.username, Some(self))
+                        }
+                        _ => Message::Toast(egui_notify::Toast::error(t!("sync.error", error = e))),
+                    }
+                }
+            },
+        );
+    }
+
+    fn name(&self) -> String {
+        match self {
+            Sync::Achievements { game, .. } => t!("sync.achievements_job", game = game.name()),
+            Sync::Restore { game, .. } => t!("sync.restore_job", game = game.name()),
+            Sync::Pulls { game, .. } => t!("sync.pulls_job", game = game.name()),
+        }
+    }
+
+    fn run(&self, stardb: &stardb::Client, user: Option<&User>) -> anyhow::Result<String> {
+        let id = user.map(|u| u.id.as_str());
+
+        match self {
+            Sync::Achievements {
+                game,
+                sync_mode,
+                achievements,
+                confirmed,
+            } => {
+                let user = user.ok_or(stardb::Error::AuthExpired)?;
+
+                let completed = stardb.completed(*game, &user.id)?;
+
+                let to_delete: Vec<u32> = completed
+                    .iter()
+                    .copied()
+                    .filter(|a| !achievements.contains(a))
+                    .collect();
+
+                let mirror = *sync_mode == SyncMode::Mirror && !to_delete.is_empty();
+                if mirror && !confirmed {
+                    return Err(Unconfirmed(to_delete.len()).into());
+                }
+
+                backup::save(*game, &user.username, &completed)?;
+
+                if mirror {
+                    stardb.remove_completed(*game, &user.id, &to_delete)?;
+                }
+
+                stardb.add_completed(*game, &user.id, achievements)?;
+
+                Ok(t!("sync.synced"))
+            }
+            Sync::Restore { game, achievements } => {
+                let user = user.ok_or(stardb::Error::AuthExpired)?;
+
+                // Restoring can be undone by restoring the state from right before
+                let completed = stardb.completed(*game, &user.id)?;
+                backup::save(*game, &user.username, &completed)?;
+
+                let to_delete: Vec<u32> = completed
+                    .into_iter()
+                    .filter(|a| !achievements.contains(a))
+                    .collect();
+
+                stardb.remove_completed(*game, &user.id, &to_delete)?;
+                stardb.add_completed(*game, &user.id, achievements)?;
+
+                Ok(t!("sync.restored"))
+            }
+            Sync::Pulls { game, url } => {
+                let import = stardb.import_pulls(*game, id, url)?;
+
+                Ok(t!("sync.synced_uid", uid = import.uid))
+            }
+        }
+    }
+}
@@ -0,0 +1,15 @@
+pub fn colors() -> super::Colors {
+    let bg = egui::Color32::from_rgb(0, 0, 0);
+    let surface = egui::Color32::from_rgb(0, 0, 0);
+    let border = egui::Color32::from_rgb(255, 255, 0);
+    let text = egui::Color32::from_rgb(255, 255, 255);
+    let accent = egui::Color32::from_rgb(0, 255, 255);
+
+    super::Colors {
+        bg,
+        surface,
+        border,
+        text,
+        accent,
+    }
+}
@@ -1,34 +1,159 @@
+use std::{fs, path::PathBuf};
+
+use crate::i18n::t;
+
 mod classic;
 mod dark;
+mod high_contrast;
 mod light;
 
-#[derive(Default, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[derive(Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum Theme {
     #[default]
     Dark,
     Light,
     Classic,
+    HighContrast,
+    /// Dark or light, whatever the os uses
+    System,
+    /// A theme file in the themes dir, by name
+    Custom(String),
 }
 
+#[derive(Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Colors {
-    bg: egui::Color32,
-    surface: egui::Color32,
-    border: egui::Color32,
-    text: egui::Color32,
-    accent: egui::Color32,
+    #[serde(with = "hex")]
+    pub bg: egui::Color32,
+    #[serde(with = "hex")]
+    pub surface: egui::Color32,
+    #[serde(with = "hex")]
+    pub border: egui::Color32,
+    #[serde(with = "hex")]
+    pub text: egui::Color32,
+    #[serde(with = "hex")]
+    pub accent: egui::Color32,
+}
+
+/// A theme loaded from the themes dir
+#[derive(Clone)]
+pub struct Custom {
+    pub name: String,
+    pub path: PathBuf,
+    pub colors: Colors,
 }
 
 impl Theme {
-    pub fn style(self) -> egui::Style {
+    pub fn colors(&self, custom: &[Custom], system: Option<egui::Theme>) -> Colors {
+        match self {
+            Theme::Dark => dark::colors(),
+            Theme::Light => light::colors(),
+            Theme::Classic => classic::colors(),
+            Theme::HighContrast => high_contrast::colors(),
+            Theme::System => match system {
+                Some(egui::Theme::Light) => light::colors(),
+                _ => dark::colors(),
+            },
+            Theme::Custom(name) => custom
+                .iter()
+                .find(|c| c.name == *name)
+                .map(|c| c.colors)
+                .unwrap_or_else(dark::colors),
+        }
+    }
+}
+
+impl Colors {
+    pub fn style(&self) -> egui::Style {
         let mut style = style();
+        style.visuals = visuals(self);
+        style
+    }
+}
 
-        style.visuals = match self {
-            Theme::Dark => visuals(&dark::colors()),
-            Theme::Light => visuals(&light::colors()),
-            Theme::Classic => visuals(&classic::colors()),
-        };
+pub fn dir() -> anyhow::Result<PathBuf> {
+    crate::storage_dir("themes")
+}
 
-        style
+/// The `.json` and `.toml` files in the themes dir. Broken ones are logged and skipped
+pub fn load() -> Vec<Custom> {
+    let entries = match dir().and_then(|dir| Ok(fs::read_dir(dir)?)) {
+        Ok(entries) => entries,
+        Err(e) => {
+            tracing::error!("Couldn't read the themes dir: {e}");
+            return Vec::new();
+        }
+    };
+
+    let mut custom: Vec<_> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_string();
+
+            match read(&path) {
+                Ok(Some(colors)) => Some(Custom { name, path, colors }),
+                Ok(None) => None,
+                Err(e) => {
+                    tracing::error!("Invalid theme {}: {e}", path.display());
+                    None
+                }
+            }
+        })
+        .collect();
+
+    custom.sort_by(|a, b| a.name.cmp(&b.name));
+
+    custom
+}
+
+/// Saves `colors` as `name`, into the file that theme came from if there is one
+pub fn save(name: &str, colors: &Colors, custom: &[Custom]) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !name.is_empty()
+            && !name.starts_with('.')
+            && !name.contains(['/', '\\', ':', '*', '?', '"', '<', '>', '|']),
+        "{}",
+        t!("theme.invalid_name")
+    );
+
+    let path = match custom.iter().find(|c| c.name == name) {
+        Some(c) => c.path.clone(),
+        None => dir()?.join(format!("{name}.toml")),
+    };
+
+    let text = match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => serde_json::to_string_pretty(colors)?,
+        _ => toml::to_string_pretty(colors)?,
+    };
+
+    fs::write(path, text)?;
+
+    Ok(())
+}
+
+fn read(path: &std::path::Path) -> anyhow::Result<Option<Colors>> {
+    let colors = match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => serde_json::from_str(&fs::read_to_string(path)?)?,
+        Some("toml") => toml::from_str(&fs::read_to_string(path)?)?,
+        _ => return Ok(None),
+    };
+
+    Ok(Some(colors))
+}
+
+/// Colors are written as `#rrggbb`
+mod hex {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(color: &egui::Color32, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&egui::ecolor::HexColor::Hex6(*color).to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<egui::Color32, D::Error> {
+        let hex = String::deserialize(d)?;
+
+        egui::Color32::from_hex(&hex)
+            .map_err(|_| serde::de::Error::custom(format!("invalid color {hex}")))
     }
 }
 
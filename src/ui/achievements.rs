@@ -1,141 +1,297 @@
-use std::thread;
+use std::collections::HashSet;
+
+use egui_remixicon::icons;
 
 use crate::{
-    app::{App, Message, State},
-    games,
+    app::{Achievements, App, Message, State, SyncMode},
+    export,
+    i18n::{t, tn},
+    stardb,
+    sync::Sync,
+    ui,
 };
 
-pub fn show(ui: &mut egui::Ui, achievements: &[u32], app: &App) {
-    let key = match app.game {
-        games::Game::Hsr => "hsr_achievements",
-        games::Game::Gi => "gi_achievements",
-        _ => unimplemented!(),
-    };
+pub fn show(ui: &mut egui::Ui, achievements: &Achievements, app: &App) {
+    let mut selected_achievements: Vec<u32> = achievements
+        .completed
+        .iter()
+        .copied()
+        .filter(|id| achievements.selected.contains(id))
+        .collect();
+    selected_achievements.sort();
+
+    ui.label(t!("achievements.finished"));
+
+    ui.horizontal(|ui| {
+        let mut search = achievements.search.clone();
+        if ui::focus::first(
+            ui.add(egui::TextEdit::singleline(&mut search).hint_text(t!("common.search"))),
+        )
+        .changed()
+        {
+            update(app, achievements, |a| a.search = search);
+        }
 
-    ui.label("Finished");
+        let mut show_completed = achievements.show_completed;
+        if ui
+            .checkbox(&mut show_completed, t!("achievements.completed"))
+            .changed()
+        {
+            update(app, achievements, |a| a.show_completed = show_completed);
+        }
+
+        let mut show_missing = achievements.show_missing;
+        if ui
+            .checkbox(&mut show_missing, t!("achievements.missing"))
+            .changed()
+        {
+            update(app, achievements, |a| a.show_missing = show_missing);
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.label(t!(
+            "achievements.summary",
+            completed = achievements.completed.len(),
+            total = achievements.metadata.len(),
+            selected = selected_achievements.len(),
+        ));
+
+        if ui.button(t!("achievements.select_all")).clicked() {
+            update(app, achievements, |a| {
+                a.selected = a.completed.iter().copied().collect()
+            });
+        }
+
+        if ui.button(t!("achievements.select_none")).clicked() {
+            update(app, achievements, |a| a.selected.clear());
+        }
+    });
+
+    egui::ScrollArea::vertical()
+        .max_height(250.0)
+        .auto_shrink([false, true])
+        .show(ui, |ui| browser(ui, achievements, app));
 
     if ui
-        .button(format!(
-            "Copy {} achievements to clipboard",
-            achievements.len()
-        ))
+        .button(tn!("achievements.copy", selected_achievements.len()))
         .clicked()
     {
-        if let Err(e) = arboard::Clipboard::new()
-            .and_then(|mut c| c.set_text(serde_json::json!({ key: achievements }).to_string()))
-        {
+        if let Err(e) = arboard::Clipboard::new().and_then(|mut c| {
+            c.set_text(export::achievements_json(app.game, &selected_achievements))
+        }) {
             app.message_tx
                 .send(Message::GoTo(State::Error(e.to_string())))
                 .unwrap();
         } else {
             app.message_tx
-                .send(Message::Toast(egui_notify::Toast::success("Copied")))
+                .send(Message::Toast(egui_notify::Toast::success(t!(
+                    "common.copied"
+                ))))
                 .unwrap();
         }
     }
 
-    ui.hyperlink_to("Click here to import", "https://stardb.gg/import");
+    ui.horizontal(|ui| {
+        if ui.button(t!("common.save_json")).clicked() {
+            ui::export::save(
+                app,
+                &export::file_name(app.game, "achievements", "json"),
+                "JSON",
+                "json",
+                &export::achievements_json(app.game, &selected_achievements),
+            );
+        }
+
+        if ui.button(t!("common.save_csv")).clicked() {
+            ui::export::save(
+                app,
+                &export::file_name(app.game, "achievements", "csv"),
+                "CSV",
+                "csv",
+                &export::achievements_csv(&achievements.completed, &achievements.metadata),
+            );
+        }
+    });
+
+    ui::export::directory(ui, app);
+
+    ui.hyperlink_to(t!("achievements.import"), "https://stardb.gg/import");
+
+    if let Some(user) = app.user() {
+        let mut sync_mode = app.config.sync_mode;
+
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut sync_mode, SyncMode::Merge, t!("sync.merge"))
+                .on_hover_text(t!("sync.merge_hint"));
+            ui.radio_value(&mut sync_mode, SyncMode::Mirror, t!("sync.mirror"))
+                .on_hover_text(t!("sync.mirror_hint"));
+        });
+
+        if sync_mode != app.config.sync_mode {
+            app.message_tx.send(Message::SyncMode(sync_mode)).unwrap();
+        }
 
-    if let Some(user) = &app.user {
         if ui
-            .button(format!("Sync to account: \"{}\"", user.username))
+            .button(t!("achievements.sync", username = user.username))
             .clicked()
         {
             app.message_tx
-                .send(Message::Toast(egui_notify::Toast::info("Syncing")))
+                .send(Message::Toast(egui_notify::Toast::info(t!(
+                    "achievements.syncing"
+                ))))
                 .unwrap();
 
-            let prefix = match app.game {
-                games::Game::Hsr => "",
-                games::Game::Gi => "gi/",
-                _ => unimplemented!(),
-            };
-
-            let url = format!("https://stardb.gg/api/users/me/{prefix}achievements/completed");
-
-            {
-                let message_tx = app.message_tx.clone();
-                let id = user.id.clone();
-                let achievements = achievements.to_vec();
-
-                thread::spawn(move || {
-                    let to_delete: Vec<i32> = match ureq::get(&url).header("Cookie", &id).call() {
-                        Ok(r) => {
-                            if r.status() == 200 {
-                                r.into_body().read_json().unwrap()
-                            } else {
-                                message_tx
-                                    .send(Message::Toast(egui_notify::Toast::error(
-                                        "Error. Try Relogging",
-                                    )))
-                                    .unwrap();
-
-                                return;
-                            }
-                        }
-                        Err(e) => {
-                            message_tx
-                                .send(Message::Toast(egui_notify::Toast::error(format!(
-                                    "Error: {e}"
-                                ))))
-                                .unwrap();
-                            return;
-                        }
-                    };
-
-                    match ureq::delete(&url)
-                        .header("Cookie", &id)
-                        .force_send_body()
-                        .send_json(to_delete)
-                    {
-                        Ok(r) => {
-                            if r.status() != 200 {
-                                message_tx
-                                    .send(Message::Toast(egui_notify::Toast::error(
-                                        "Error. Try Relogging",
-                                    )))
-                                    .unwrap();
-
-                                return;
-                            }
-                        }
-                        Err(e) => {
-                            message_tx
-                                .send(Message::Toast(egui_notify::Toast::error(format!(
-                                    "Error: {e}"
-                                ))))
-                                .unwrap();
-                            return;
-                        }
-                    };
-
-                    match ureq::put(&url)
-                        .header("Cookie", &id)
-                        .send_json(achievements)
-                    {
-                        Ok(r) => {
-                            if r.status() == 200 {
-                                message_tx
-                                    .send(Message::Toast(egui_notify::Toast::success("Synced")))
-                                    .unwrap();
-                            } else {
-                                message_tx
-                                    .send(Message::Toast(egui_notify::Toast::error(
-                                        "Error. Try Relogging",
-                                    )))
-                                    .unwrap();
-                            }
-                        }
-                        Err(e) => {
-                            message_tx
-                                .send(Message::Toast(egui_notify::Toast::error(format!(
-                                    "Error: {e}"
-                                ))))
-                                .unwrap();
-                        }
+            Sync::Achievements {
+                game: app.game,
+                sync_mode,
+                achievements: selected_achievements,
+                confirmed: false,
+            }
+            .spawn(&app.stardb, Some(user), &app.message_tx);
+        }
+
+        if let Some((sync, count)) = &achievements.confirm_mirror {
+            ui.label(tn!("sync.mirror_confirm", *count));
+
+            ui.horizontal(|ui| {
+                if ui.button(t!("sync.mirror_anyway")).clicked() {
+                    let mut sync = sync.clone();
+                    if let Sync::Achievements { confirmed, .. } = &mut sync {
+                        *confirmed = true;
                     }
-                });
+
+                    sync.spawn(&app.stardb, Some(user), &app.message_tx);
+                    update(app, achievements, |a| a.confirm_mirror = None);
+                }
+
+                if ui.button(t!("common.cancel")).clicked() {
+                    update(app, achievements, |a| a.confirm_mirror = None);
+                }
+            });
+        }
+
+        if let Some(backup) = &achievements.backup {
+            let restore_button = ui
+                .button(t!(
+                    "achievements.restore",
+                    time = backup
+                        .timestamp
+                        .with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M")
+                ))
+                .on_hover_text(t!("achievements.restore_hint"));
+
+            if restore_button.clicked() {
+                app.message_tx
+                    .send(Message::Toast(egui_notify::Toast::info(t!(
+                        "achievements.restoring"
+                    ))))
+                    .unwrap();
+
+                Sync::Restore {
+                    game: app.game,
+                    achievements: backup.achievements.clone(),
+                }
+                .spawn(&app.stardb, Some(user), &app.message_tx);
             }
         }
     }
 }
+
+fn browser(ui: &mut egui::Ui, achievements: &Achievements, app: &App) {
+    let completed: HashSet<_> = achievements.completed.iter().copied().collect();
+    let search = achievements.search.to_lowercase();
+
+    let mut series: Vec<(&str, Vec<&stardb::Achievement>)> = Vec::new();
+
+    for achievement in &achievements.metadata {
+        let is_completed = completed.contains(&achievement.id);
+
+        if (is_completed && !achievements.show_completed)
+            || (!is_completed && !achievements.show_missing)
+        {
+            continue;
+        }
+
+        if !search.is_empty()
+            && !achievement.name.to_lowercase().contains(&search)
+            && !achievement.series_name.to_lowercase().contains(&search)
+            && !achievement.id.to_string().contains(&search)
+        {
+            continue;
+        }
+
+        match series
+            .iter_mut()
+            .find(|(name, _)| *name == achievement.series_name)
+        {
+            Some((_, series)) => series.push(achievement),
+            None => series.push((&achievement.series_name, vec![achievement])),
+        }
+    }
+
+    if series.is_empty() {
+        ui.label(t!("achievements.no_matches"));
+    }
+
+    for (name, series) in series {
+        let series_completed = series.iter().filter(|a| completed.contains(&a.id)).count();
+
+        egui::CollapsingHeader::new(format!("{name} ({series_completed}/{})", series.len()))
+            .id_salt(name)
+            .open((!search.is_empty()).then_some(true))
+            .show(ui, |ui| {
+                for achievement in series {
+                    ui.horizontal(|ui| {
+                        if completed.contains(&achievement.id) {
+                            let mut checked = achievements.selected.contains(&achievement.id);
+
+                            let checkbox = ui.checkbox(&mut checked, "");
+                            checkbox.widget_info(|| {
+                                egui::WidgetInfo::selected(
+                                    egui::WidgetType::Checkbox,
+                                    true,
+                                    checked,
+                                    &achievement.name,
+                                )
+                            });
+
+                            if checkbox.changed() {
+                                update(app, achievements, |a| {
+                                    if checked {
+                                        a.selected.insert(achievement.id);
+                                    } else {
+                                        a.selected.remove(&achievement.id);
+                                    }
+                                });
+                            }
+                        } else {
+                            let missing = ui
+                                .label(icons::CLOSE_LINE)
+                                .on_hover_text(t!("achievements.missing"));
+                            missing.widget_info(|| {
+                                egui::WidgetInfo::labeled(
+                                    egui::WidgetType::Label,
+                                    true,
+                                    t!("achievements.missing"),
+                                )
+                            });
+                        }
+
+                        ui.label(&achievement.name);
+                        ui.weak(achievement.id.to_string());
+                    });
+                }
+            });
+    }
+}
+
+fn update(app: &App, achievements: &Achievements, f: impl FnOnce(&mut Achievements)) {
+    let mut achievements = achievements.clone();
+    f(&mut achievements);
+
+    app.message_tx
+        .send(Message::GoTo(State::Achievements(achievements)))
+        .unwrap();
+}
@@ -0,0 +1,43 @@
+use crate::{
+    app::{App, Message, State},
+    archive::Archive,
+    i18n::t,
+    stats::Stats,
+    ui,
+};
+
+pub fn show(ui: &mut egui::Ui, uids: &[String], app: &App) {
+    if uids.is_empty() {
+        ui.label(t!("archive.empty"));
+        return;
+    }
+
+    for (i, uid) in uids.iter().enumerate() {
+        ui.horizontal(|ui| {
+            ui.label(t!("common.uid", uid = uid));
+
+            let open = ui.button(t!("common.open"));
+            if i == 0 {
+                ui::focus::first(open.clone());
+            }
+
+            if open.clicked() {
+                let state = match Archive::open().and_then(|a| a.history(app.game, uid)) {
+                    Ok(history) => State::PullHistory(history),
+                    Err(e) => State::Error(e.to_string()),
+                };
+
+                app.message_tx.send(Message::GoTo(state)).unwrap();
+            }
+
+            if ui.button(t!("common.statistics")).clicked() {
+                let state = match Archive::open().and_then(|a| a.history(app.game, uid)) {
+                    Ok(history) => State::Stats(Stats::new(&history)),
+                    Err(e) => State::Error(e.to_string()),
+                };
+
+                app.message_tx.send(Message::GoTo(state)).unwrap();
+            }
+        });
+    }
+}
@@ -1,3 +1,5 @@
+use crate::i18n::t;
+
 pub fn show(ui: &mut egui::Ui, s: &str) {
-    ui.label(format!("Error: {s}"));
+    ui.label(t!("error.message", error = s));
 }
@@ -0,0 +1,54 @@
+use crate::{
+    app::{App, Message, State},
+    i18n::t,
+};
+
+/// Asks where to save `contents`, starting in the default export directory
+pub fn save(app: &App, file_name: &str, filter: &str, extension: &str, contents: &str) {
+    let mut dialog = rfd::FileDialog::new()
+        .set_file_name(file_name)
+        .add_filter(filter, &[extension]);
+
+    if let Some(dir) = &app.config.export_dir {
+        dialog = dialog.set_directory(dir);
+    }
+
+    let Some(path) = dialog.save_file() else {
+        return;
+    };
+
+    if let Err(e) = std::fs::write(&path, contents) {
+        app.message_tx
+            .send(Message::GoTo(State::Error(e.to_string())))
+            .unwrap();
+    } else {
+        app.message_tx
+            .send(Message::Toast(egui_notify::Toast::success(t!(
+                "export.saved_to",
+                path = path.display()
+            ))))
+            .unwrap();
+    }
+}
+
+pub fn directory(ui: &mut egui::Ui, app: &App) {
+    ui.horizontal(|ui| {
+        ui.label(t!(
+            "export.directory",
+            path = app
+                .config
+                .export_dir
+                .as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or(t!("common.none"))
+        ));
+
+        if ui.button(t!("export.change")).clicked() {
+            let Some(path) = rfd::FileDialog::new().pick_folder() else {
+                return;
+            };
+
+            app.message_tx.send(Message::ExportDir(path)).unwrap();
+        }
+    });
+}
@@ -0,0 +1,83 @@
+const VISIBLE_ID: &str = "focus_visible";
+const SCREEN_ID: &str = "focus_screen";
+
+/// Outlines the focused widget while the keyboard is in use, like `:focus-visible` in css.
+/// The themes draw focused widgets like hovered ones and text edits not at all
+pub fn show(ctx: &egui::Context) {
+    let id = egui::Id::new(VISIBLE_ID);
+
+    let keyboard = ctx.input(|i| {
+        if i.pointer.any_pressed() {
+            Some(false)
+        } else if [
+            egui::Key::Tab,
+            egui::Key::ArrowUp,
+            egui::Key::ArrowDown,
+            egui::Key::ArrowLeft,
+            egui::Key::ArrowRight,
+        ]
+        .into_iter()
+        .any(|key| i.key_pressed(key))
+        {
+            Some(true)
+        } else {
+            None
+        }
+    });
+
+    let visible = ctx.data_mut(|d| {
+        let visible = d.get_temp_mut_or_default::<bool>(id);
+
+        if let Some(keyboard) = keyboard {
+            *visible = keyboard;
+        }
+
+        *visible
+    });
+
+    let Some(response) = ctx
+        .memory(|m| m.focused())
+        .and_then(|focused| ctx.read_response(focused))
+    else {
+        return;
+    };
+
+    if !visible || !response.enabled() {
+        return;
+    }
+
+    let visuals = ctx.style().visuals.clone();
+
+    ctx.layer_painter(egui::LayerId::new(egui::Order::Tooltip, id))
+        .rect_stroke(
+            response.rect.expand(2.0),
+            visuals.widgets.inactive.corner_radius,
+            egui::Stroke::new(2.0, visuals.hyperlink_color),
+            egui::StrokeKind::Outside,
+        );
+}
+
+/// Called when another screen is shown, so its first widget takes the focus if the keyboard
+/// is in use
+pub fn entered(ctx: &egui::Context) {
+    ctx.data_mut(|d| {
+        let visible = d
+            .get_temp::<bool>(egui::Id::new(VISIBLE_ID))
+            .unwrap_or(false);
+        d.insert_temp(egui::Id::new(SCREEN_ID), visible);
+    });
+}
+
+/// Focuses the first widget it's called with after `entered`
+pub fn first(response: egui::Response) -> egui::Response {
+    let focus = response
+        .ctx
+        .data_mut(|d| d.remove_temp::<bool>(egui::Id::new(SCREEN_ID)))
+        .unwrap_or(false);
+
+    if focus {
+        response.request_focus();
+    }
+
+    response
+}
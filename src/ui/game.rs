@@ -2,41 +2,81 @@ use egui_remixicon::icons;
 
 use crate::{
     app::{App, Message, State},
+    archive::Archive,
     games,
+    i18n::t,
+    stats::Stats,
+    ui,
 };
 
 pub fn show(ui: &mut egui::Ui, app: &App) {
     match app.game {
         games::Game::Hsr => {
-            if ui.button("Achievement Exporter").clicked() {
-                app.game.achievements(&app.message_tx);
+            if ui::focus::first(ui.button(t!("game.achievement_exporter"))).clicked() {
+                let id =
+                    app.game
+                        .achievements(&app.stardb, &app.config.interfaces, &app.message_tx);
                 app.message_tx
-                    .send(Message::GoTo(State::Waiting("Preparing".to_string())))
+                    .send(Message::Wait(t!("game.preparing"), id))
                     .unwrap();
             }
 
-            if ui.button("Warp Exporter").clicked() {
+            if ui.button(t!("game.warp_exporter")).clicked() {
                 app.message_tx.send(Message::GoTo(State::PullMenu)).unwrap();
             }
+
+            if ui.button(t!("game.pull_statistics")).clicked() {
+                statistics(app);
+            }
         }
         games::Game::Gi => {
-            ui.colored_label(ui.visuals().hyperlink_color, format!("{} Make sure, that you fresh started the game before using the achievement exporter!!", icons::INFORMATION_LINE));
+            ui.colored_label(
+                ui.visuals().hyperlink_color,
+                format!("{} {}", icons::INFORMATION_LINE, t!("game.fresh_start")),
+            );
 
-            if ui.button("Achievement Exporter").clicked() {
-                app.game.achievements(&app.message_tx);
+            if ui::focus::first(ui.button(t!("game.achievement_exporter"))).clicked() {
+                let id =
+                    app.game
+                        .achievements(&app.stardb, &app.config.interfaces, &app.message_tx);
                 app.message_tx
-                    .send(Message::GoTo(State::Waiting("Preparing".to_string())))
+                    .send(Message::Wait(t!("game.preparing"), id))
                     .unwrap();
             }
 
-            if ui.button("Wish Exporter").clicked() {
+            if ui.button(t!("game.wish_exporter")).clicked() {
                 app.message_tx.send(Message::GoTo(State::PullMenu)).unwrap();
             }
+
+            if ui.button(t!("game.pull_statistics")).clicked() {
+                statistics(app);
+            }
         }
         games::Game::Zzz => {
-            if ui.button("Signal Exporter").clicked() {
+            if ui::focus::first(ui.button(t!("game.signal_exporter"))).clicked() {
                 app.message_tx.send(Message::GoTo(State::PullMenu)).unwrap();
             }
+
+            if ui.button(t!("game.pull_statistics")).clicked() {
+                statistics(app);
+            }
         }
     }
 }
+
+/// Opens the statistics of the only archived account, or the list to pick one from
+fn statistics(app: &App) {
+    let state = match Archive::open().and_then(|archive| {
+        let uids = archive.uids(app.game)?;
+
+        Ok(match uids.as_slice() {
+            [uid] => State::Stats(Stats::new(&archive.history(app.game, uid)?)),
+            _ => State::Archive(uids),
+        })
+    }) {
+        Ok(state) => state,
+        Err(e) => State::Error(e.to_string()),
+    };
+
+    app.message_tx.send(Message::GoTo(state)).unwrap();
+}
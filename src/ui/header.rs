@@ -1,8 +1,9 @@
 use egui_remixicon::icons;
 
 use crate::{
-    app::{App, Message, State},
-    games, themes,
+    app::{App, Message, State, ThemeEditor},
+    i18n::t,
+    themes,
 };
 
 pub fn show(ctx: &egui::Context, ui: &mut egui::Ui, app: &App) {
@@ -11,15 +12,28 @@ pub fn show(ctx: &egui::Context, ui: &mut egui::Ui, app: &App) {
 
         ui.add_space(32.0);
 
-        let waiting = matches!(app.state, State::Waiting(_));
+        let waiting = matches!(
+            app.state,
+            State::Waiting(_) | State::Unlock(_) | State::Restarting(_)
+        );
 
         let heading_text = match app.state {
-            State::Game | State::Achievements(_) | State::PullMenu => match app.game {
-                games::Game::Hsr => "Honkai Star Rail",
-                games::Game::Gi => "Genshin Impact",
-                games::Game::Zzz => "Zenless Zone Zero",
-            },
-            _ => "Menu",
+            State::Game
+            | State::Achievements(..)
+            | State::PullMenu
+            | State::PullUrls(_)
+            | State::PullHistory(_)
+            | State::Archive(_)
+            | State::Stats(_) => app.game.name(),
+            State::History(..) => t!("menu.history"),
+            State::Settings(_) => t!("menu.settings"),
+            State::Proxy(_) => t!("header.proxy"),
+            State::Logs(_) => t!("menu.logs"),
+            State::ThemeEditor(_) => t!("header.theme"),
+            State::Updates(_) | State::UpdateAvailable(_) | State::ReleaseNotes(_) => {
+                t!("header.updates")
+            }
+            _ => t!("header.menu"),
         };
 
         let heading = ui.add_enabled(
@@ -27,7 +41,14 @@ pub fn show(ctx: &egui::Context, ui: &mut egui::Ui, app: &App) {
             egui::Label::new(
                 egui::RichText::new(format!("{} {heading_text}", icons::ARROW_LEFT_UP_LINE))
                     .heading(),
-            ),
+            )
+            .selectable(false)
+            .sense(egui::Sense::click()),
+        );
+        describe(
+            &heading,
+            egui::WidgetType::Link,
+            &t!("header.back", title = heading_text),
         );
 
         if heading.hovered() {
@@ -40,7 +61,7 @@ pub fn show(ctx: &egui::Context, ui: &mut egui::Ui, app: &App) {
         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
             ui.add_space(32.0);
 
-            let height = if let Some(user) = &app.user {
+            let height = if let Some(user) = app.user() {
                 let mut icon_format = egui::TextFormat::simple(
                     egui::FontId::proportional(20.0),
                     ui.visuals().text_color(),
@@ -58,6 +79,11 @@ pub fn show(ctx: &egui::Context, ui: &mut egui::Ui, app: &App) {
                 username_job.append(&user.username, 8.0, text_format.clone());
 
                 let account_button = ui.add_enabled(!waiting, egui::Button::new(username_job));
+                describe(
+                    &account_button,
+                    egui::WidgetType::Button,
+                    &t!("header.account", username = user.username),
+                );
                 let account_popup_id = account_button.id.with("popup");
 
                 let is_account_popup_open = ui.memory(|m| m.is_popup_open(account_popup_id));
@@ -70,9 +96,11 @@ pub fn show(ctx: &egui::Context, ui: &mut egui::Ui, app: &App) {
                         egui::AboveOrBelow::Below,
                         egui::PopupCloseBehavior::CloseOnClick,
                         |ui| {
+                            let mut focus = focus_first(ui, account_popup_id);
+
                             ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
                             ui.visuals_mut().widgets.inactive.bg_stroke.color =
-                                ui.visuals().widgets.active.bg_stroke.color;
+                                ui.visuals().widgets.hovered.bg_stroke.color;
 
                             let mut icon_format = egui::TextFormat::simple(
                                 egui::FontId::proportional(20.0),
@@ -88,15 +116,47 @@ pub fn show(ctx: &egui::Context, ui: &mut egui::Ui, app: &App) {
 
                             let mut website_job = egui::text::LayoutJob::default();
                             website_job.append(icons::LINK, 0.0, icon_format.clone());
-                            website_job.append("Website", 8.0, text_format.clone());
+                            website_job.append(&t!("header.website"), 8.0, text_format.clone());
+
+                            let mut add_account_job = egui::text::LayoutJob::default();
+                            add_account_job.append(icons::USER_ADD_LINE, 0.0, icon_format.clone());
+                            add_account_job.append(&t!("header.add_account"), 8.0, text_format.clone());
+
+                            let mut security_job = egui::text::LayoutJob::default();
+                            security_job.append(icons::LOCK_LINE, 0.0, icon_format.clone());
+                            security_job.append(&t!("header.security"), 8.0, text_format.clone());
 
                             let mut logout_job = egui::text::LayoutJob::default();
                             logout_job.append(icons::LOGOUT_BOX_LINE, 0.0, icon_format.clone());
-                            logout_job.append("Logout", 8.0, text_format.clone());
+                            logout_job.append(&t!("header.logout"), 8.0, text_format.clone());
+
+                            for other in &app.users {
+                                let mut account_job = egui::text::LayoutJob::default();
+                                account_job.append(
+                                    icons::ACCOUNT_CIRCLE_LINE,
+                                    0.0,
+                                    icon_format.clone(),
+                                );
+                                account_job.append(&other.username, 8.0, text_format.clone());
+
+                                let selected = other.username == user.username;
+                                let response = ui.selectable_label(selected, account_job);
+
+                                if entry(ui, response, &other.username, Some(selected), &mut focus)
+                                    .clicked()
+                                {
+                                    app.message_tx
+                                        .send(Message::Account(other.username.clone()))
+                                        .unwrap();
+                                }
+                            }
 
-                            if ui.button(website_job).clicked() {
+                            ui.separator();
+
+                            let website = ui.button(website_job);
+                            if entry(ui, website, &t!("header.website"), None, &mut focus).clicked() {
                                 let url = match app.state {
-                                    State::Achievements(_) => app.game.achievement_url(),
+                                    State::Achievements(..) => app.game.achievement_url(),
                                     State::PullMenu | State::Pulls(_) => app.game.pull_url(),
                                     _ => "https://stardb.gg".to_string(),
                                 };
@@ -110,8 +170,32 @@ pub fn show(ctx: &egui::Context, ui: &mut egui::Ui, app: &App) {
                                 }
                             }
 
-                            if ui.button(logout_job).clicked() {
-                                app.message_tx.send(Message::Logout).unwrap();
+                            let add_account = ui.button(add_account_job);
+                            if entry(ui, add_account, &t!("header.add_account"), None, &mut focus)
+                                .clicked()
+                            {
+                                app.message_tx
+                                    .send(Message::GoTo(State::Login(String::new(), String::new())))
+                                    .unwrap();
+                            }
+
+                            let security = ui.button(security_job);
+                            if entry(ui, security, &t!("header.security"), None, &mut focus)
+                                .clicked()
+                            {
+                                app.message_tx
+                                    .send(Message::GoTo(State::Security(
+                                        String::new(),
+                                        String::new(),
+                                    )))
+                                    .unwrap();
+                            }
+
+                            let logout = ui.button(logout_job);
+                            if entry(ui, logout, &t!("header.logout"), None, &mut focus).clicked() {
+                                app.message_tx
+                                    .send(Message::Logout(user.username.clone()))
+                                    .unwrap();
                             }
                         },
                     );
@@ -121,6 +205,8 @@ pub fn show(ctx: &egui::Context, ui: &mut egui::Ui, app: &App) {
                     ui.memory_mut(|mem| mem.toggle_popup(account_popup_id));
                 }
 
+                popup_keyboard(ui, &account_button, account_popup_id, is_account_popup_open);
+
                 account_button.rect.height()
             } else {
                 ui.scope(|ui| {
@@ -147,9 +233,10 @@ pub fn show(ctx: &egui::Context, ui: &mut egui::Ui, app: &App) {
 
                     let mut login_job = egui::text::LayoutJob::default();
                     login_job.append(icons::LOGIN_BOX_LINE, 0.0, icon_format.clone());
-                    login_job.append("Login", 8.0, text_format.clone());
+                    login_job.append(&t!("common.login"), 8.0, text_format.clone());
 
                     let login_button = ui.add_enabled(!waiting, egui::Button::new(login_job));
+                    describe(&login_button, egui::WidgetType::Button, &t!("common.login"));
                     if login_button.clicked() {
                         app.message_tx
                             .send(Message::GoTo(State::Login(String::new(), String::new())))
@@ -168,7 +255,8 @@ pub fn show(ctx: &egui::Context, ui: &mut egui::Ui, app: &App) {
             );
             let button = button.min_size(egui::vec2(48.0, height));
 
-            let color_button = ui.add(button);
+            let color_button = ui.add(button).on_hover_text(t!("header.theme"));
+            describe(&color_button, egui::WidgetType::Button, &t!("header.theme"));
             let color_popup_id = color_button.id.with("popup");
 
             let is_color_popup_open = ui.memory(|m| m.is_popup_open(color_popup_id));
@@ -181,9 +269,11 @@ pub fn show(ctx: &egui::Context, ui: &mut egui::Ui, app: &App) {
                     egui::AboveOrBelow::Below,
                     egui::PopupCloseBehavior::CloseOnClick,
                     |ui| {
+                        let mut focus = focus_first(ui, color_popup_id);
+
                         ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
                         ui.visuals_mut().widgets.inactive.bg_stroke.color =
-                            ui.visuals().widgets.active.bg_stroke.color;
+                            ui.visuals().widgets.hovered.bg_stroke.color;
 
                         let mut icon_format = egui::TextFormat::simple(
                             egui::FontId::proportional(20.0),
@@ -197,33 +287,76 @@ pub fn show(ctx: &egui::Context, ui: &mut egui::Ui, app: &App) {
                         );
                         text_format.valign = egui::Align::Center;
 
-                        let mut dark_job = egui::text::LayoutJob::default();
-                        dark_job.append(icons::MOON_LINE, 0.0, icon_format.clone());
-                        dark_job.append("Dark", 8.0, text_format.clone());
-
-                        let mut light_job = egui::text::LayoutJob::default();
-                        light_job.append(icons::SUN_LINE, 0.0, icon_format.clone());
-                        light_job.append("Light", 8.0, text_format.clone());
-
-                        let mut classic_job = egui::text::LayoutJob::default();
-                        classic_job.append(icons::BARD_LINE, 0.0, icon_format.clone());
-                        classic_job.append("Classic", 8.0, text_format.clone());
-
-                        let mut theme = app.theme;
-
-                        ui.selectable_value(&mut theme, themes::Theme::Dark, dark_job);
-                        ui.selectable_value(&mut theme, themes::Theme::Light, light_job);
-                        ui.selectable_value(&mut theme, themes::Theme::Classic, classic_job);
-
-                        app.message_tx.send(Message::Theme(theme)).unwrap();
+                        let mut choices = vec![
+                            (themes::Theme::Dark, icons::MOON_LINE, t!("header.dark")),
+                            (themes::Theme::Light, icons::SUN_LINE, t!("header.light")),
+                            (themes::Theme::Classic, icons::BARD_LINE, t!("header.classic")),
+                            (
+                                themes::Theme::HighContrast,
+                                icons::CONTRAST_2_LINE,
+                                t!("header.high_contrast"),
+                            ),
+                            (themes::Theme::System, icons::COMPUTER_LINE, t!("header.system")),
+                        ];
+
+                        for custom in &app.themes {
+                            choices.push((
+                                themes::Theme::Custom(custom.name.clone()),
+                                icons::PALETTE_LINE,
+                                custom.name.clone(),
+                            ));
+                        }
+
+                        for (theme, icon, label) in choices {
+                            let mut job = egui::text::LayoutJob::default();
+                            job.append(icon, 0.0, icon_format.clone());
+                            job.append(&label, 8.0, text_format.clone());
+
+                            let selected = theme == app.theme;
+                            let response = ui.selectable_label(selected, job);
+
+                            if entry(ui, response, &label, Some(selected), &mut focus).clicked()
+                                && !selected
+                            {
+                                app.message_tx.send(Message::Theme(theme)).unwrap();
+                            }
+                        }
+
+                        ui.separator();
+
+                        let mut edit_job = egui::text::LayoutJob::default();
+                        edit_job.append(icons::EDIT_LINE, 0.0, icon_format.clone());
+                        edit_job.append(&t!("header.edit_theme"), 8.0, text_format.clone());
+
+                        let edit = ui.add_enabled(!waiting, egui::Button::new(edit_job));
+                        if entry(ui, edit, &t!("header.edit_theme"), None, &mut focus).clicked() {
+                            let name = match &app.theme {
+                                themes::Theme::Custom(name) => name.clone(),
+                                _ => String::new(),
+                            };
+
+                            app.message_tx
+                                .send(Message::GoTo(State::ThemeEditor(ThemeEditor {
+                                    name,
+                                    colors: app.theme.colors(&app.themes, ui.ctx().system_theme()),
+                                })))
+                                .unwrap();
+                        }
                     },
                 );
             }
 
             if color_button.clicked() {
+                if !is_color_popup_open {
+                    // Picks up theme files that were added while the app is running
+                    app.message_tx.send(Message::ReloadThemes).unwrap();
+                }
+
                 ui.memory_mut(|mem| mem.toggle_popup(color_popup_id));
             }
 
+            popup_keyboard(ui, &color_button, color_popup_id, is_color_popup_open);
+
             ui.style_mut().spacing.button_padding = old_button_padding;
             let text = egui::Color32::BLACK;
             let accent = egui::Color32::from_rgb(250, 204, 21);
@@ -252,11 +385,12 @@ pub fn show(ctx: &egui::Context, ui: &mut egui::Ui, app: &App) {
 
             let mut lootbar_job = egui::text::LayoutJob::default();
             lootbar_job.append(icons::LINKS_LINE, 0.0, icon_format.clone());
-            lootbar_job.append("Lootbar", 8.0, text_format.clone());
+            lootbar_job.append(&t!("header.lootbar"), 8.0, text_format.clone());
 
-            let button = egui::Button::new(lootbar_job);
+            let button = ui.add(egui::Button::new(lootbar_job));
+            describe(&button, egui::WidgetType::Button, &t!("header.lootbar"));
 
-            if ui.add(button).clicked() {
+            if button.clicked() {
                 if let Err(e) = open::that("https://lootbar.gg/index?utm_source=Affiliate&utm_medium=Affiliate&utm_campaign=lHBYqExxGc") {
                     app.message_tx
                         .send(Message::Toast(egui_notify::Toast::error(format!("{e}"))))
@@ -266,3 +400,62 @@ pub fn show(ctx: &egui::Context, ui: &mut egui::Ui, app: &App) {
         });
     });
 }
+
+/// Names a widget for screen readers, which would otherwise read out the icon glyphs
+fn describe(response: &egui::Response, typ: egui::WidgetType, label: &str) {
+    let enabled = response.enabled();
+    response.widget_info(|| egui::WidgetInfo::labeled(typ, enabled, label));
+}
+
+/// An entry of a header popup. The popup only closes on clicks by itself, so this closes it
+/// when the entry is used with the keyboard too
+fn entry(
+    ui: &egui::Ui,
+    response: egui::Response,
+    label: &str,
+    selected: Option<bool>,
+    focus: &mut bool,
+) -> egui::Response {
+    let enabled = response.enabled();
+    response.widget_info(|| match selected {
+        Some(selected) => {
+            egui::WidgetInfo::selected(egui::WidgetType::SelectableLabel, enabled, selected, label)
+        }
+        None => egui::WidgetInfo::labeled(egui::WidgetType::Button, enabled, label),
+    });
+
+    if std::mem::take(focus) {
+        response.request_focus();
+    }
+
+    if response.clicked() {
+        ui.memory_mut(|m| m.close_popup());
+    }
+
+    response
+}
+
+/// Whether the popup was just opened with the keyboard, so its first entry takes the focus
+fn focus_first(ui: &egui::Ui, popup_id: egui::Id) -> bool {
+    ui.data_mut(|d| d.remove_temp::<bool>(popup_id))
+        .unwrap_or(false)
+}
+
+/// Moves the keyboard focus into a popup when it's opened with the keyboard and back to its
+/// button when it's closed with it
+fn popup_keyboard(ui: &egui::Ui, button: &egui::Response, popup_id: egui::Id, was_open: bool) {
+    let keyboard = ui.input(|i| {
+        i.key_pressed(egui::Key::Enter)
+            || i.key_pressed(egui::Key::Space)
+            || i.key_pressed(egui::Key::Escape)
+    });
+    let open = ui.memory(|m| m.is_popup_open(popup_id));
+
+    if keyboard && was_open && !open {
+        button.request_focus();
+    }
+
+    if keyboard && !was_open && open {
+        ui.data_mut(|d| d.insert_temp(popup_id, true));
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::HashSet;
+
+use crate::{
+    app::{App, Message, State, SyncMode},
+    export,
+    history::{self, Export, Snapshot},
+    i18n::{t, tn},
+    sync::Sync,
+    ui,
+};
+
+pub fn show(
+    ui: &mut egui::Ui,
+    snapshots: &[Snapshot],
+    a: Option<usize>,
+    b: Option<usize>,
+    app: &App,
+) {
+    if snapshots.is_empty() {
+        ui.label(t!("history.empty"));
+        return;
+    }
+
+    if let (Some(a), Some(b)) = (a, b) {
+        diff(ui, &snapshots[a], &snapshots[b]);
+        ui.separator();
+    } else {
+        ui.label(t!("history.pick"));
+    }
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for (i, snapshot) in snapshots.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "{} {} {}",
+                    snapshot
+                        .timestamp
+                        .with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M"),
+                    snapshot.game.name(),
+                    snapshot.export.name(),
+                ));
+
+                if let Some(uid) = &snapshot.uid {
+                    ui.label(t!("common.uid", uid = uid));
+                }
+
+                if let Export::Achievements(achievements) = &snapshot.export {
+                    ui.label(format!("({})", achievements.len()));
+                }
+            });
+
+            ui.horizontal(|ui| {
+                let copy_button = ui.button(t!("common.copy"));
+                if i == 0 {
+                    ui::focus::first(copy_button.clone());
+                }
+
+                if copy_button.clicked() {
+                    copy(snapshot, app);
+                }
+
+                if ui.button(t!("common.open")).clicked() {
+                    app.message_tx.send(Message::Game(snapshot.game)).unwrap();
+
+                    match &snapshot.export {
+                        Export::Achievements(achievements) => {
+                            let id = snapshot.game.browse_achievements(
+                                &app.stardb,
+                                achievements.clone(),
+                                &app.message_tx,
+                            );
+                            app.message_tx
+                                .send(Message::Wait(t!("common.loading"), id))
+                                .unwrap();
+                        }
+                        Export::Pulls(url) => app
+                            .message_tx
+                            .send(Message::GoTo(State::Pulls(url.clone())))
+                            .unwrap(),
+                    }
+                }
+
+                if let Some(user) = app.game_user(snapshot.game)
+                    && ui
+                        .button(t!("achievements.sync", username = user.username))
+                        .clicked()
+                {
+                    app.message_tx
+                        .send(Message::Toast(egui_notify::Toast::info(t!(
+                            "achievements.syncing"
+                        ))))
+                        .unwrap();
+
+                    let sync = match &snapshot.export {
+                        // Merging, a mirror needs a confirmation on the achievements screen
+                        Export::Achievements(achievements) => Sync::Achievements {
+                            game: snapshot.game,
+                            sync_mode: SyncMode::Merge,
+                            achievements: achievements.clone(),
+                            confirmed: false,
+                        },
+                        Export::Pulls(url) => Sync::Pulls {
+                            game: snapshot.game,
+                            url: url.clone(),
+                        },
+                    };
+                    sync.spawn(&app.stardb, Some(user), &app.message_tx);
+                }
+
+                if ui.selectable_label(a == Some(i), t!("history.a")).clicked() {
+                    app.message_tx
+                        .send(Message::GoTo(State::History(
+                            snapshots.to_vec(),
+                            Some(i),
+                            b,
+                        )))
+                        .unwrap();
+                }
+
+                if ui.selectable_label(b == Some(i), t!("history.b")).clicked() {
+                    app.message_tx
+                        .send(Message::GoTo(State::History(
+                            snapshots.to_vec(),
+                            a,
+                            Some(i),
+                        )))
+                        .unwrap();
+                }
+
+                if ui.button(t!("history.delete")).clicked() {
+                    let state = match history::delete(snapshot).and_then(|_| history::load()) {
+                        Ok(snapshots) => State::History(snapshots, None, None),
+                        Err(e) => State::Error(e.to_string()),
+                    };
+
+                    app.message_tx.send(Message::GoTo(state)).unwrap();
+                }
+            });
+
+            ui.separator();
+        }
+    });
+}
+
+fn copy(snapshot: &Snapshot, app: &App) {
+    let text = match &snapshot.export {
+        Export::Achievements(achievements) => {
+            export::achievements_json(snapshot.game, achievements)
+        }
+        Export::Pulls(url) => url.clone(),
+    };
+
+    if let Err(e) = arboard::Clipboard::new().and_then(|mut c| c.set_text(text)) {
+        app.message_tx
+            .send(Message::GoTo(State::Error(e.to_string())))
+            .unwrap();
+    } else {
+        app.message_tx
+            .send(Message::Toast(egui_notify::Toast::success(t!(
+                "common.copied"
+            ))))
+            .unwrap();
+    }
+}
+
+fn diff(ui: &mut egui::Ui, a: &Snapshot, b: &Snapshot) {
+    if a.game != b.game {
+        ui.label(t!("history.different_games"));
+        return;
+    }
+
+    match (&a.export, &b.export) {
+        (Export::Achievements(a), Export::Achievements(b)) => {
+            let a: HashSet<_> = a.iter().copied().collect();
+            let b: HashSet<_> = b.iter().copied().collect();
+
+            let mut added: Vec<_> = b.difference(&a).copied().collect();
+            let mut removed: Vec<_> = a.difference(&b).copied().collect();
+            added.sort();
+            removed.sort();
+
+            ui.collapsing(tn!("history.added", added.len()), |ui| {
+                ui.label(join(&added));
+            });
+            ui.collapsing(tn!("history.missing", removed.len()), |ui| {
+                ui.label(join(&removed));
+            });
+        }
+        (Export::Pulls(url_a), Export::Pulls(url_b)) => {
+            if a.uid != b.uid {
+                ui.label(t!(
+                    "history.different_uids",
+                    a = a.uid.clone().unwrap_or(t!("common.none")),
+                    b = b.uid.clone().unwrap_or(t!("common.none")),
+                ));
+            } else if url_a == url_b {
+                ui.label(t!("history.same_url"));
+            } else {
+                ui.label(t!("history.same_uid"));
+            }
+        }
+        _ => {
+            ui.label(t!("history.different_kinds"));
+        }
+    }
+}
+
+fn join(ids: &[u32]) -> String {
+    ids.iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,24 @@
+use crate::{i18n::t, jobs::Job};
+
+pub fn show(ui: &mut egui::Ui, jobs: &[Job]) {
+    for job in jobs {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label(&job.name);
+
+            if !job.progress.is_empty() {
+                ui.weak(&job.progress);
+            }
+
+            if let Some(fraction) = job.fraction {
+                ui.add(egui::ProgressBar::new(fraction).desired_width(120.0));
+            }
+
+            let cancel =
+                ui.add_enabled(!job.is_cancelled(), egui::Button::new(t!("common.cancel")));
+            if cancel.clicked() {
+                job.cancel();
+            }
+        });
+    }
+}
@@ -1,22 +1,26 @@
-use std::{sync::mpsc, thread};
+use std::sync::mpsc;
 
-use crate::app::{App, Message, State, User};
+use crate::{
+    app::{App, Message, State, User},
+    i18n::t,
+    jobs, stardb, ui,
+};
 
 pub fn show(ui: &mut egui::Ui, username: &str, password: &str, app: &App) {
     let mut username = username.to_string();
     let mut password = password.to_string();
 
-    ui.label("Username:");
-    let username_edit = ui.text_edit_singleline(&mut username);
+    ui.label(t!("login.username"));
+    let username_edit = ui::focus::first(ui.text_edit_singleline(&mut username));
 
-    ui.label("Password:");
+    ui.label(t!("login.password"));
     let password_edit = ui.add(egui::TextEdit::singleline(&mut password).password(true));
 
-    if ui.button("Login").clicked() {
-        login(&username, &password, &app.message_tx);
+    if ui.button(t!("common.login")).clicked() {
+        let id = login(&username, &password, &app.stardb, &app.message_tx);
 
         app.message_tx
-            .send(Message::GoTo(State::Waiting("Loggin In".to_string())))
+            .send(Message::Wait(t!("login.logging_in"), id))
             .unwrap();
     } else if username_edit.changed() || password_edit.changed() {
         app.message_tx
@@ -25,41 +29,31 @@ pub fn show(ui: &mut egui::Ui, username: &str, password: &str, app: &App) {
     }
 }
 
-fn login(username: &str, password: &str, message_tx: &mpsc::Sender<Message>) {
+fn login(
+    username: &str,
+    password: &str,
+    stardb: &stardb::Client,
+    message_tx: &mpsc::Sender<Message>,
+) -> u64 {
     let username = username.to_string();
     let password = password.to_string();
-    let message_tx = message_tx.clone();
-
-    thread::spawn(move || {
-        let json = serde_json::json!({
-            "username": username,
-            "password": password
-        });
-
-        let id = ureq::post("https://stardb.gg/api/users/auth/login")
-            .send_json(json)
-            .ok()
-            .and_then(|r| {
-                r.headers()
-                    .get("Set-Cookie")
-                    .and_then(|h| h.to_str().ok())
-                    .and_then(|id| id.split(';').next())
-                    .map(|s| s.to_string())
-            });
-
-        if let Some(id) = id {
-            let username = username.to_string();
-
-            let user = User { id, username };
-
-            message_tx.send(Message::User(Some(user))).unwrap();
-            message_tx.send(Message::GoTo(State::Menu)).unwrap();
-        } else {
-            message_tx
-                .send(Message::GoTo(State::Error(
-                    "There was an error during the login".to_string(),
-                )))
-                .unwrap();
-        }
-    });
+    let stardb = stardb.clone();
+
+    jobs::spawn(
+        message_tx,
+        t!("login.job", username = username),
+        move |_| {
+            let id = stardb.login(&username, &password)?;
+
+            Ok(User { id, username })
+        },
+        |result| match result {
+            Ok(user) => Message::LoggedIn(user),
+            Err(e) => {
+                tracing::error!("Login failed: {e}");
+
+                Message::GoTo(State::Error(t!("login.failed")))
+            }
+        },
+    )
 }
@@ -0,0 +1,144 @@
+use crate::{
+    app::{App, Logs, Message, State},
+    config::LogLevel,
+    diagnostics,
+    i18n::t,
+    jobs, logs, ui,
+};
+
+pub fn show(ui: &mut egui::Ui, logs: &Logs, app: &App) {
+    let mut level = logs.level;
+    let mut search = logs.search.clone();
+    let mut reloaded = None;
+
+    ui.horizontal(|ui| {
+        ui.label(t!("logs.level"));
+        let filter = egui::ComboBox::from_id_salt("log_filter")
+            .selected_text(level.name())
+            .show_ui(ui, |ui| {
+                for l in LogLevel::ALL {
+                    ui.selectable_value(&mut level, l, l.name());
+                }
+            });
+        ui::focus::first(filter.response);
+
+        ui.label(t!("logs.search"));
+        ui.text_edit_singleline(&mut search);
+    });
+
+    ui.horizontal(|ui| {
+        if ui.button(t!("logs.reload")).clicked() {
+            match load(app) {
+                Ok(lines) => reloaded = Some(lines),
+                Err(e) => app
+                    .message_tx
+                    .send(Message::GoTo(State::Error(e.to_string())))
+                    .unwrap(),
+            }
+        }
+
+        if ui.button(t!("logs.open_folder")).clicked()
+            && let Err(e) = logs::dir().and_then(|dir| Ok(open::that(dir)?))
+        {
+            app.message_tx
+                .send(Message::Toast(egui_notify::Toast::error(e.to_string())))
+                .unwrap();
+        }
+
+        if ui
+            .button(t!("logs.bundle"))
+            .on_hover_text(t!("logs.bundle_hint"))
+            .clicked()
+        {
+            bundle(app);
+        }
+    });
+
+    ui.separator();
+
+    let needle = search.to_lowercase();
+    let lines: Vec<_> = logs
+        .lines
+        .iter()
+        .filter(|l| l.level <= level)
+        .filter(|l| needle.is_empty() || l.text.to_lowercase().contains(&needle))
+        .collect();
+
+    let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+
+    egui::ScrollArea::both().stick_to_bottom(true).show_rows(
+        ui,
+        row_height,
+        lines.len(),
+        |ui, rows| {
+            for line in &lines[rows] {
+                let color = match line.level {
+                    LogLevel::Error => ui.visuals().error_fg_color,
+                    LogLevel::Warn => ui.visuals().warn_fg_color,
+                    _ => ui.visuals().text_color(),
+                };
+
+                ui.label(egui::RichText::new(&line.text).monospace().color(color));
+            }
+        },
+    );
+
+    if level != logs.level || search != logs.search || reloaded.is_some() {
+        app.message_tx
+            .send(Message::GoTo(State::Logs(Logs {
+                lines: reloaded.unwrap_or(logs.lines.clone()),
+                level,
+                search,
+            })))
+            .unwrap();
+    }
+}
+
+/// Recent log lines with secrets already removed
+pub fn load(app: &App) -> anyhow::Result<Vec<logs::Line>> {
+    let redactor = app.redactor();
+
+    Ok(logs::recent(3)?
+        .into_iter()
+        .map(|line| logs::Line {
+            text: redactor.redact(&line.text),
+            ..line
+        })
+        .collect())
+}
+
+fn bundle(app: &App) {
+    let mut dialog = rfd::FileDialog::new()
+        .set_file_name(diagnostics::file_name())
+        .add_filter("Zip", &["zip"]);
+
+    if let Some(dir) = &app.config.export_dir {
+        dialog = dialog.set_directory(dir);
+    }
+
+    let Some(path) = dialog.save_file() else {
+        return;
+    };
+
+    let config = app.config.clone();
+    let redactor = app.redactor();
+
+    jobs::spawn(
+        &app.message_tx,
+        t!("logs.bundle_job"),
+        move |_| {
+            diagnostics::bundle(&path, &config, redactor)?;
+            Ok(path)
+        },
+        |result| match result {
+            Ok(path) => Message::Toast(egui_notify::Toast::success(t!(
+                "export.saved_to",
+                path = path.display()
+            ))),
+            Err(e) => Message::Toast(egui_notify::Toast::error(t!(
+                "logs.bundle_failed",
+                error = e
+            ))),
+        },
+    );
+}
@@ -1,18 +1,58 @@
 use crate::{
-    app::{App, Message},
+    app::{App, Logs, Message, Settings, State},
+    config::LogLevel,
     games::Game,
+    history,
+    i18n::t,
+    ui,
 };
 
 pub fn show(ui: &mut egui::Ui, app: &App) {
-    if ui.button("Honkai: Star Rail").clicked() {
+    if ui::focus::first(ui.button(t!("menu.hsr"))).clicked() {
         app.message_tx.send(Message::Game(Game::Hsr)).unwrap();
     }
 
-    if ui.button("Genshin Impact").clicked() {
+    if ui.button(t!("menu.gi")).clicked() {
         app.message_tx.send(Message::Game(Game::Gi)).unwrap();
     }
 
-    if ui.button("Zenless Zone Zero").clicked() {
+    if ui.button(t!("menu.zzz")).clicked() {
         app.message_tx.send(Message::Game(Game::Zzz)).unwrap();
     }
+
+    ui.separator();
+
+    if ui.button(t!("menu.history")).clicked() {
+        match history::load() {
+            Ok(snapshots) => app
+                .message_tx
+                .send(Message::GoTo(State::History(snapshots, None, None)))
+                .unwrap(),
+            Err(e) => app
+                .message_tx
+                .send(Message::GoTo(State::Error(e.to_string())))
+                .unwrap(),
+        }
+    }
+
+    if ui.button(t!("menu.settings")).clicked() {
+        app.message_tx
+            .send(Message::GoTo(State::Settings(Box::new(Settings::new(
+                app.config.clone(),
+            )))))
+            .unwrap();
+    }
+
+    if ui.button(t!("menu.logs")).clicked() {
+        let state = match ui::logs::load(app) {
+            Ok(lines) => State::Logs(Logs {
+                lines,
+                level: LogLevel::Info,
+                search: String::new(),
+            }),
+            Err(e) => State::Error(e.to_string()),
+        };
+
+        app.message_tx.send(Message::GoTo(state)).unwrap();
+    }
 }
@@ -1,9 +1,25 @@
 pub mod achievements;
+pub mod archive;
 pub mod error;
+pub mod export;
+pub mod focus;
 pub mod game;
 pub mod header;
+pub mod history;
+pub mod jobs;
 pub mod login;
+pub mod logs;
 pub mod menu;
+pub mod proxy;
+pub mod pull_history;
 pub mod pull_menu;
+pub mod pull_urls;
 pub mod pulls;
+pub mod relogin;
+pub mod security;
+pub mod settings;
+pub mod stats;
+pub mod theme;
+pub mod unlock;
+pub mod update;
 pub mod waiting;
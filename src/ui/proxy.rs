@@ -0,0 +1,47 @@
+use crate::{
+    app::{App, Message, State},
+    http::{Proxy, ProxyMode},
+    i18n::t,
+    ui,
+};
+
+pub fn show(ui: &mut egui::Ui, proxy: &Proxy, app: &App) {
+    let mut edited = proxy.clone();
+
+    ui.horizontal(|ui| {
+        ui::focus::first(ui.radio_value(
+            &mut edited.mode,
+            ProxyMode::Environment,
+            t!("proxy.system"),
+        ))
+        .on_hover_text(t!("proxy.system_hint"));
+        ui.radio_value(&mut edited.mode, ProxyMode::Direct, t!("proxy.direct"));
+        ui.radio_value(&mut edited.mode, ProxyMode::Custom, t!("proxy.custom"));
+    });
+
+    if edited.mode == ProxyMode::Custom {
+        ui.label(t!("proxy.url"));
+        ui.text_edit_singleline(&mut edited.url);
+
+        ui.label(t!("proxy.username"));
+        ui.text_edit_singleline(&mut edited.username);
+
+        ui.label(t!("proxy.password"));
+        ui.add(egui::TextEdit::singleline(&mut edited.password).password(true));
+    }
+
+    let save = ui.add_enabled(
+        edited != app.config.proxy,
+        egui::Button::new(t!("common.save")),
+    );
+
+    if save.clicked() {
+        app.message_tx.send(Message::Proxy(edited.clone())).unwrap();
+    }
+
+    if edited != *proxy {
+        app.message_tx
+            .send(Message::GoTo(State::Proxy(edited)))
+            .unwrap();
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{
+    app::{App, Message, State},
+    export,
+    gacha::History,
+    games::Game,
+    i18n::{t, tn},
+    stats::Stats,
+    ui,
+};
+
+pub fn show(ui: &mut egui::Ui, history: &History, app: &App) {
+    ui.label(tn!(
+        "pull_history.summary",
+        history.pulls.len(),
+        uid = history.uid
+    ));
+
+    egui::Grid::new("banners").striped(true).show(ui, |ui| {
+        for (banner, name) in history.game.banners() {
+            let pulls = history
+                .pulls
+                .iter()
+                .filter(|p| p.banner(history.game) == *banner)
+                .count();
+
+            ui.label(t!(*name));
+            ui.label(pulls.to_string());
+            ui.end_row();
+        }
+    });
+
+    if ui.button(t!("common.statistics")).clicked() {
+        app.message_tx
+            .send(Message::GoTo(State::Stats(Stats::new(history))))
+            .unwrap();
+    }
+
+    ui.separator();
+
+    if ui.button(t!("pull_history.save_uigf_v4")).clicked() {
+        match export::uigf_v4(history) {
+            Ok(json) => ui::export::save(
+                app,
+                &export::file_name(history.game, "uigf", "json"),
+                "UIGF",
+                "json",
+                &json,
+            ),
+            Err(e) => app
+                .message_tx
+                .send(Message::Toast(egui_notify::Toast::error(e.to_string())))
+                .unwrap(),
+        }
+    }
+
+    match history.game {
+        Game::Hsr => {
+            if ui.button(t!("pull_history.save_srgf")).clicked() {
+                ui::export::save(
+                    app,
+                    &export::file_name(history.game, "srgf", "json"),
+                    "SRGF",
+                    "json",
+                    &export::srgf(history),
+                );
+            }
+        }
+        Game::Gi => {
+            if ui.button(t!("pull_history.save_uigf_v3")).clicked() {
+                ui::export::save(
+                    app,
+                    &export::file_name(history.game, "uigf_v3", "json"),
+                    "UIGF",
+                    "json",
+                    &export::uigf_v3(history),
+                );
+            }
+        }
+        Game::Zzz => {}
+    }
+
+    ui::export::directory(ui, app);
+}
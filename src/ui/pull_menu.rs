@@ -1,56 +1,30 @@
-use std::thread;
-
 use crate::{
     app::{App, Message, State},
-    games,
+    archive::Archive,
+    i18n::t,
+    ui,
 };
 
 pub fn show(ui: &mut egui::Ui, app: &App) {
-    match app.game {
-        games::Game::Hsr => {
-            ui.label(format!(
-                "Path: {}",
-                app.paths
-                    .hsr
-                    .as_ref()
-                    .map(|p| p.display().to_string())
-                    .unwrap_or("None".to_string())
-            ));
-        }
-        games::Game::Gi => {
-            ui.label(format!(
-                "Path: {}",
-                app.paths
-                    .gi
-                    .as_ref()
-                    .map(|p| p.display().to_string())
-                    .unwrap_or("None".to_string())
-            ));
-        }
-        games::Game::Zzz => {
-            ui.label(format!(
-                "Path: {}",
-                app.paths
-                    .zzz
-                    .as_ref()
-                    .map(|p| p.display().to_string())
-                    .unwrap_or("None".to_string())
-            ));
-        }
-    }
+    let path = &app.config.game(app.game).path;
 
-    if ui.button("Automatic").clicked() {
-        match app.game.game_path() {
-            Ok(path) => app.message_tx.send(Message::Path(path)).unwrap(),
-            Err(e) => app
-                .message_tx
-                .send(Message::GoTo(State::Error(e.to_string())))
-                .unwrap(),
-        }
+    ui.label(t!(
+        "pull_menu.path",
+        path = path
+            .as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or(t!("common.none"))
+    ));
+
+    if ui::focus::first(ui.button(t!("pull_menu.automatic"))).clicked() {
+        app.find_game_path(app.game);
     }
 
     if ui
-        .button("Manual selection (e.g. D:\\Star Rail\\Games\\StarRail_Data)")
+        .button(t!(
+            "pull_menu.manual",
+            example = "D:\\Star Rail\\Games\\StarRail_Data"
+        ))
         .clicked()
     {
         if let Some(path) = rfd::FileDialog::new().pick_folder() {
@@ -58,28 +32,26 @@ pub fn show(ui: &mut egui::Ui, app: &App) {
         }
     }
 
-    if let Some(path) = match app.game {
-        games::Game::Hsr => &app.paths.hsr,
-        games::Game::Gi => &app.paths.gi,
-        games::Game::Zzz => &app.paths.zzz,
-    } {
-        if ui.button("Get Url").clicked() {
-            let message_tx = app.message_tx.clone();
-            let path = path.clone();
-
-            thread::spawn(move || {
-                match games::pulls_from_game_path(&path) {
-                    Ok(url) => message_tx.send(Message::GoTo(State::Pulls(url))),
-                    Err(e) => message_tx.send(Message::GoTo(State::Error(e.to_string()))),
-                }
-                .unwrap()
-            });
+    if let Some(path) = path {
+        if ui.button(t!("pull_menu.get_url")).clicked() {
+            let id = app.pull_urls(app.game, Some(path.clone()));
 
             app.message_tx
-                .send(Message::GoTo(State::Waiting("Running".to_string())))
+                .send(Message::Wait(t!("common.running"), id))
                 .unwrap();
         }
     } else {
-        ui.add_enabled(false, egui::Button::new("Get Url"));
+        ui.add_enabled(false, egui::Button::new(t!("pull_menu.get_url")));
+    }
+
+    ui.separator();
+
+    if ui.button(t!("pull_menu.archived")).clicked() {
+        let state = match Archive::open().and_then(|a| a.uids(app.game)) {
+            Ok(uids) => State::Archive(uids),
+            Err(e) => State::Error(e.to_string()),
+        };
+
+        app.message_tx.send(Message::GoTo(state)).unwrap();
     }
 }
@@ -0,0 +1,71 @@
+use crate::{
+    app::{App, Message, State},
+    games::PullUrl,
+    i18n::{t, tn},
+};
+
+pub fn show(ui: &mut egui::Ui, pull_urls: &[PullUrl], app: &App) {
+    let valid = pull_urls.iter().filter(|p| p.error.is_none()).count();
+
+    ui.label(tn!("pull_urls.found", pull_urls.len(), valid = valid));
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        egui::Grid::new("pull_urls").striped(true).show(ui, |ui| {
+            for pull_url in pull_urls {
+                ui.label(
+                    pull_url
+                        .timestamp
+                        .with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M")
+                        .to_string(),
+                );
+
+                match &pull_url.error {
+                    None => {
+                        ui.label(t!(
+                            "common.uid",
+                            uid = pull_url.uid.clone().unwrap_or(t!("common.unknown"))
+                        ));
+                        ui.label(&pull_url.region);
+                    }
+                    Some(e) => {
+                        ui.colored_label(ui.visuals().error_fg_color, e);
+                        ui.label(&pull_url.region);
+                    }
+                }
+
+                if ui.button(t!("common.copy")).clicked() {
+                    copy(&pull_url.url, app);
+                }
+
+                if ui
+                    .add_enabled(
+                        pull_url.error.is_none(),
+                        egui::Button::new(t!("pull_urls.use")),
+                    )
+                    .clicked()
+                {
+                    app.message_tx
+                        .send(Message::GoTo(State::Pulls(pull_url.url.clone())))
+                        .unwrap();
+                }
+
+                ui.end_row();
+            }
+        });
+    });
+}
+
+fn copy(url: &str, app: &App) {
+    if let Err(e) = arboard::Clipboard::new().and_then(|mut c| c.set_text(url)) {
+        app.message_tx
+            .send(Message::GoTo(State::Error(e.to_string())))
+            .unwrap();
+    } else {
+        app.message_tx
+            .send(Message::Toast(egui_notify::Toast::success(t!(
+                "common.copied"
+            ))))
+            .unwrap();
+    }
+}
@@ -1,63 +1,128 @@
 use crate::{
     app::{App, Message, State},
-    games,
+    archive::Archive,
+    export, gacha, games,
+    i18n::{t, tn},
+    jobs,
+    sync::Sync,
+    ui,
 };
 
 pub fn show(ui: &mut egui::Ui, url: &str, app: &App) {
-    ui.label("Finished");
+    ui.label(t!("achievements.finished"));
 
-    if ui.button("Copy url to clipboard").clicked() {
+    let info = gacha::UrlInfo::parse(url);
+    ui.label(t!("pulls.region", region = info.name()));
+    if let Some(lang) = &info.lang {
+        ui.label(t!("pulls.language", lang = lang));
+    }
+    if let Some(authkey_ver) = &info.authkey_ver {
+        ui.label(t!("pulls.authkey_version", version = authkey_ver));
+    }
+
+    if ui::focus::first(ui.button(t!("pulls.copy_url"))).clicked() {
         if let Err(e) = arboard::Clipboard::new().and_then(|mut c| c.set_text(url)) {
             app.message_tx
                 .send(Message::GoTo(State::Error(e.to_string())))
                 .unwrap();
         } else {
             app.message_tx
-                .send(Message::Toast(egui_notify::Toast::success("Copied")))
+                .send(Message::Toast(egui_notify::Toast::success(t!(
+                    "common.copied"
+                ))))
                 .unwrap();
         }
     }
 
+    if ui.button(t!("pulls.save_url")).clicked() {
+        ui::export::save(
+            app,
+            &export::file_name(app.game, "pull_url", "txt"),
+            "Text",
+            "txt",
+            url,
+        );
+    }
+
+    ui::export::directory(ui, app);
+
     let import_url = match app.game {
         games::Game::Hsr => "https://stardb.gg/warp-import",
         games::Game::Gi => "https://stardb.gg/genshin/wish-import",
         games::Game::Zzz => "https://stardb.gg/zzz/signal-import",
     };
 
-    ui.hyperlink_to("Click here to import", import_url);
+    ui.hyperlink_to(t!("achievements.import"), import_url);
 
-    if ui.button("Sync to stardb").clicked() {
-        let import_url = match app.game {
-            games::Game::Hsr => "https://stardb.gg/api/warps-import",
-            games::Game::Gi => "https://stardb.gg/api/gi/wishes-import",
-            games::Game::Zzz => "https://stardb.gg/api/zzz/signals-import",
-        };
+    if ui.button(t!("pulls.sync")).clicked() {
+        app.message_tx
+            .send(Message::Toast(egui_notify::Toast::info(t!(
+                "achievements.syncing"
+            ))))
+            .unwrap();
 
-        let request = if let Some(user) = &app.user {
-            ureq::post(import_url).header("Cookie", &user.id)
-        } else {
-            ureq::post(import_url)
-        };
-
-        match request.send_json(serde_json::json!({"url": url})) {
-            Ok(mut r) => {
-                app.message_tx
-                    .send(Message::Toast(egui_notify::Toast::success(format!(
-                        "Synced uid {}",
-                        r.body_mut().read_json::<serde_json::Value>().unwrap()["uid"]
-                    ))))
-                    .unwrap();
-                app.message_tx
-                    .send(Message::Toast(egui_notify::Toast::success("Copied")))
-                    .unwrap();
-            }
-            Err(e) => {
-                app.message_tx
-                    .send(Message::Toast(egui_notify::Toast::error(format!(
-                        "Error: {e}"
-                    ))))
-                    .unwrap();
-            }
+        Sync::Pulls {
+            game: app.game,
+            url: url.to_string(),
         }
+        .spawn(&app.stardb, app.user(), &app.message_tx);
     }
+
+    if ui.button(t!("pulls.download")).clicked() {
+        download(url, app);
+    }
+}
+
+fn download(url: &str, app: &App) {
+    let agent = app.agent.clone();
+    let game = app.game;
+    let url = url.to_string();
+
+    let id = jobs::spawn(
+        &app.message_tx,
+        t!("pulls.downloading_job", game = game.name()),
+        move |context| download_to_archive(&agent, game, &url, context),
+        |result| match result {
+            Ok((history, new)) => {
+                tracing::info!("{new} new pulls archived");
+                Message::GoTo(State::PullHistory(history))
+            }
+            Err(e) => Message::GoTo(State::Error(t!("pulls.download_failed", error = e))),
+        },
+    );
+
+    app.message_tx
+        .send(Message::Wait(t!("pulls.downloading"), id))
+        .unwrap();
+}
+
+/// Downloads the pulls that aren't archived yet. Returns the whole archived history and how
+/// many pulls were new
+fn download_to_archive(
+    agent: &ureq::Agent,
+    game: games::Game,
+    url: &str,
+    context: &jobs::Context,
+) -> anyhow::Result<(gacha::History, usize)> {
+    let mut archive = Archive::open()?;
+
+    let history = gacha::download(
+        agent,
+        game,
+        url,
+        |pull| archive.contains(game, pull),
+        |banner, pulls| {
+            context.check()?;
+            context.progress(tn!("pulls.progress", pulls, banner = t!(banner)), None);
+            Ok(())
+        },
+    )?;
+
+    if history.uid.is_empty() {
+        return Err(anyhow::anyhow!(t!("pulls.no_pulls")));
+    }
+
+    let new = archive.insert(&history)?;
+
+    Ok((archive.history(game, &history.uid)?, new))
 }
@@ -0,0 +1,73 @@
+use std::sync::mpsc;
+
+use crate::{
+    app::{App, Message, Relogin, User},
+    i18n::t,
+    jobs, stardb,
+};
+
+pub fn show(ui: &mut egui::Ui, relogin: &Relogin, app: &App) {
+    let mut password = relogin.password.clone();
+
+    ui.colored_label(
+        ui.visuals().warn_fg_color,
+        t!("relogin.expired", username = relogin.username),
+    );
+
+    if relogin.sync.is_some() {
+        ui.label(t!("relogin.continue_sync"));
+    }
+
+    ui.horizontal(|ui| {
+        let password_edit = ui.add(egui::TextEdit::singleline(&mut password).password(true));
+
+        let enter = password_edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+        if ui.button(t!("common.login")).clicked() || enter {
+            login(relogin, &password, &app.stardb, &app.message_tx);
+        } else if ui.button(t!("relogin.dismiss")).clicked() {
+            app.message_tx.send(Message::Relogin(None)).unwrap();
+        } else if password_edit.changed() {
+            app.message_tx
+                .send(Message::Relogin(Some(Relogin {
+                    password,
+                    ..relogin.clone()
+                })))
+                .unwrap();
+        }
+    });
+}
+
+fn login(
+    relogin: &Relogin,
+    password: &str,
+    stardb: &stardb::Client,
+    message_tx: &mpsc::Sender<Message>,
+) {
+    let username = relogin.username.clone();
+    let password = password.to_string();
+    let stardb = stardb.clone();
+
+    jobs::spawn(
+        message_tx,
+        t!("login.job", username = username),
+        move |_| {
+            let id = stardb.login(&username, &password)?;
+
+            Ok(User { id, username })
+        },
+        |result| match result {
+            Ok(user) => Message::Relogged(user),
+            Err(e) => {
+                tracing::error!("Relogin failed: {e}");
+
+                let message = match e.downcast_ref() {
+                    Some(stardb::Error::AuthExpired) => t!("relogin.wrong_password"),
+                    _ => e.to_string(),
+                };
+
+                Message::Toast(egui_notify::Toast::error(message))
+            }
+        },
+    );
+}
@@ -0,0 +1,75 @@
+use crate::{
+    app::{App, Message, State},
+    credentials,
+    i18n::t,
+};
+
+pub fn show(ui: &mut egui::Ui, passphrase: &str, confirm: &str, app: &App) {
+    if app.locked.is_some() {
+        ui.label(t!("security.locked"));
+        return;
+    }
+
+    ui.label(match app.key.as_ref().map(|k| k.protection) {
+        None => t!("security.not_encrypted"),
+        Some(credentials::Protection::Passphrase) => t!("security.passphrase_encrypted"),
+        Some(credentials::Protection::Machine) => t!("security.machine_encrypted"),
+    });
+
+    ui.separator();
+
+    let mut passphrase = passphrase.to_string();
+    let mut confirm = confirm.to_string();
+
+    ui.label(t!("common.passphrase"));
+    let passphrase_edit = ui.add(egui::TextEdit::singleline(&mut passphrase).password(true));
+
+    ui.label(t!("security.confirm"));
+    let confirm_edit = ui.add(egui::TextEdit::singleline(&mut confirm).password(true));
+
+    let valid = !passphrase.is_empty() && passphrase == confirm;
+
+    if ui
+        .add_enabled(valid, egui::Button::new(t!("security.encrypt_passphrase")))
+        .clicked()
+    {
+        protect(credentials::Key::passphrase(&passphrase), app);
+    } else if passphrase_edit.changed() || confirm_edit.changed() {
+        app.message_tx
+            .send(Message::GoTo(State::Security(passphrase, confirm)))
+            .unwrap();
+    }
+
+    ui.separator();
+
+    if ui
+        .button(t!("security.encrypt_machine"))
+        .on_hover_text(t!("security.encrypt_machine_hint"))
+        .clicked()
+    {
+        protect(credentials::Key::machine(), app);
+    }
+
+    if app.key.is_some() && ui.button(t!("security.dont_encrypt")).clicked() {
+        app.message_tx.send(Message::Protect(None)).unwrap();
+        app.message_tx.send(Message::GoTo(State::Menu)).unwrap();
+    }
+}
+
+fn protect(key: anyhow::Result<credentials::Key>, app: &App) {
+    match key {
+        Ok(key) => {
+            app.message_tx.send(Message::Protect(Some(key))).unwrap();
+            app.message_tx
+                .send(Message::Toast(egui_notify::Toast::success(t!(
+                    "security.encrypted"
+                ))))
+                .unwrap();
+            app.message_tx.send(Message::GoTo(State::Menu)).unwrap();
+        }
+        Err(e) => app
+            .message_tx
+            .send(Message::GoTo(State::Error(e.to_string())))
+            .unwrap(),
+    }
+}
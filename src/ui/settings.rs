@@ -0,0 +1,327 @@
+use crate::{
+    app::{App, Message, Settings, State, SyncMode},
+    companion,
+    config::LogLevel,
+    games::Game,
+    i18n::{Language, t},
+    stardb, ui,
+};
+
+pub fn show(ui: &mut egui::Ui, settings: &Settings, app: &App) {
+    let mut edited = settings.clone();
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        ui.heading(t!("settings.capture"));
+        ui.label(t!("settings.interfaces"));
+
+        // The first widget is a checkbox, or the timeout without any interfaces
+        let mut first = true;
+
+        for interface in &settings.interfaces {
+            let mut selected = edited.config.interfaces.contains(&interface.name);
+
+            let checkbox = ui
+                .checkbox(&mut selected, &interface.description)
+                .on_hover_text(&interface.name);
+            if std::mem::take(&mut first) {
+                ui::focus::first(checkbox.clone());
+            }
+
+            if checkbox.changed() {
+                if selected {
+                    edited.config.interfaces.push(interface.name.clone());
+                } else {
+                    edited.config.interfaces.retain(|i| *i != interface.name);
+                }
+            }
+        }
+
+        // Selected interfaces that aren't connected right now
+        for name in &settings.config.interfaces {
+            if !settings.interfaces.iter().any(|i| i.name == *name) {
+                let mut selected = edited.config.interfaces.contains(name);
+
+                let checkbox =
+                    ui.checkbox(&mut selected, t!("settings.not_connected", name = name));
+                if std::mem::take(&mut first) {
+                    ui::focus::first(checkbox.clone());
+                }
+
+                if checkbox.changed() && !selected {
+                    edited.config.interfaces.retain(|i| i != name);
+                }
+            }
+        }
+
+        ui.separator();
+        ui.heading(t!("settings.network"));
+
+        ui.horizontal(|ui| {
+            ui.label(t!("settings.timeout"));
+            let timeout = ui.add(
+                egui::DragValue::new(&mut edited.config.timeout)
+                    .range(5..=300)
+                    .suffix(" s"),
+            );
+            if first {
+                ui::focus::first(timeout);
+            }
+        });
+
+        ui.label(t!("settings.stardb_url"));
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut edited.config.stardb_url);
+
+            if ui.button(t!("common.reset")).clicked() {
+                edited.config.stardb_url = stardb::DEFAULT_URL.to_string();
+            }
+        });
+
+        if std::env::var("STARDB_URL").is_ok() {
+            ui.label(t!("settings.stardb_url_overridden"));
+        }
+
+        // Leaving for another screen would throw away the draft
+        ui.add_enabled_ui(!settings.unsaved(), |ui| {
+            ui.horizontal(|ui| {
+                if ui
+                    .button(t!("header.proxy"))
+                    .on_disabled_hover_text(t!("settings.unsaved"))
+                    .clicked()
+                {
+                    app.message_tx
+                        .send(Message::GoTo(State::Proxy(app.config.proxy.clone())))
+                        .unwrap();
+                }
+
+                if ui
+                    .button(t!("header.updates"))
+                    .on_disabled_hover_text(t!("settings.unsaved"))
+                    .clicked()
+                {
+                    app.message_tx
+                        .send(Message::GoTo(State::Updates(app.config.updates.clone())))
+                        .unwrap();
+                }
+            });
+        });
+
+        ui.separator();
+        ui.heading(t!("settings.companion"));
+
+        let companion = &mut edited.config.companion;
+
+        if ui
+            .checkbox(&mut companion.enabled, t!("settings.companion_enabled"))
+            .on_hover_text(t!("settings.companion_hint"))
+            .changed()
+            && companion.enabled
+            && companion.token.is_empty()
+        {
+            companion.token = companion::new_token();
+        }
+
+        ui.add_enabled_ui(companion.enabled, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(t!("settings.companion_port"));
+                ui.add(egui::DragValue::new(&mut companion.port).range(1024..=65535));
+
+                if ui.button(t!("common.reset")).clicked() {
+                    companion.port = companion::DEFAULT_PORT;
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label(t!("settings.companion_token"));
+                ui.code(&companion.token);
+
+                if ui.button(t!("common.copy")).clicked() {
+                    let toast = match arboard::Clipboard::new()
+                        .and_then(|mut c| c.set_text(companion.token.clone()))
+                    {
+                        Ok(()) => egui_notify::Toast::success(t!("common.copied")),
+                        Err(e) => egui_notify::Toast::error(e.to_string()),
+                    };
+
+                    app.message_tx.send(Message::Toast(toast)).unwrap();
+                }
+
+                if ui
+                    .button(t!("settings.companion_new_token"))
+                    .on_hover_text(t!("settings.companion_new_token_hint"))
+                    .clicked()
+                {
+                    companion.token = companion::new_token();
+                }
+            });
+
+            ui.label(t!("settings.companion_origins"));
+
+            let mut origins = companion.origins.join("\n");
+            if ui.text_edit_multiline(&mut origins).changed() {
+                companion.origins = origins.split('\n').map(|o| o.to_string()).collect();
+            }
+        });
+
+        ui.separator();
+        ui.heading(t!("settings.general"));
+
+        ui.horizontal(|ui| {
+            ui.label(t!("settings.language"));
+            egui::ComboBox::from_id_salt("language")
+                .selected_text(edited.config.language.name())
+                .show_ui(ui, |ui| {
+                    for language in Language::ALL {
+                        ui.selectable_value(&mut edited.config.language, language, language.name());
+                    }
+                });
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(t!("settings.ui_scale"));
+            ui.add(
+                egui::Slider::new(&mut edited.config.ui_scale, 0.5..=3.0)
+                    .step_by(0.05)
+                    .custom_formatter(|scale, _| format!("{:.0}%", scale * 100.0)),
+            );
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(t!("settings.log_level"));
+            egui::ComboBox::from_id_salt("log_level")
+                .selected_text(edited.config.log_level.name())
+                .show_ui(ui, |ui| {
+                    for level in LogLevel::ALL {
+                        ui.selectable_value(&mut edited.config.log_level, level, level.name());
+                    }
+                });
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(t!("settings.sync_mode"));
+            ui.radio_value(
+                &mut edited.config.sync_mode,
+                SyncMode::Merge,
+                t!("sync.merge"),
+            );
+            ui.radio_value(
+                &mut edited.config.sync_mode,
+                SyncMode::Mirror,
+                t!("sync.mirror"),
+            );
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(t!(
+                "export.directory",
+                path = edited
+                    .config
+                    .export_dir
+                    .as_ref()
+                    .map(|p| p.display().to_string())
+                    .unwrap_or(t!("common.none"))
+            ));
+
+            if ui.button(t!("export.change")).clicked()
+                && let Some(path) = rfd::FileDialog::new().pick_folder()
+            {
+                edited.config.export_dir = Some(path);
+            }
+
+            if ui.button(t!("settings.clear")).clicked() {
+                edited.config.export_dir = None;
+            }
+        });
+
+        for game in [Game::Hsr, Game::Gi, Game::Zzz] {
+            ui.separator();
+            ui.heading(game.name());
+
+            let game_config = edited.config.game_mut(game);
+
+            ui.horizontal(|ui| {
+                ui.label(t!(
+                    "pull_menu.path",
+                    path = game_config
+                        .path
+                        .as_ref()
+                        .map(|p| p.display().to_string())
+                        .unwrap_or(t!("common.none"))
+                ));
+
+                if ui.button(t!("settings.clear")).clicked() {
+                    game_config.path = None;
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label(t!("settings.account"));
+                egui::ComboBox::from_id_salt(game.id())
+                    .selected_text(
+                        game_config
+                            .account
+                            .clone()
+                            .unwrap_or(t!("settings.first_account")),
+                    )
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(
+                            &mut game_config.account,
+                            None,
+                            t!("settings.first_account"),
+                        );
+
+                        for user in &app.users {
+                            ui.selectable_value(
+                                &mut game_config.account,
+                                Some(user.username.clone()),
+                                &user.username,
+                            );
+                        }
+                    });
+            });
+        }
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            let save = ui.add_enabled(edited.unsaved(), egui::Button::new(t!("common.save")));
+
+            if save.clicked() {
+                app.message_tx
+                    .send(Message::SaveSettings(Box::new(edited.clone())))
+                    .unwrap();
+            }
+
+            if ui
+                .add_enabled(
+                    !settings.confirm_reset,
+                    egui::Button::new(t!("settings.reset")),
+                )
+                .on_hover_text(t!("settings.reset_hint"))
+                .clicked()
+            {
+                edited.confirm_reset = true;
+            }
+        });
+
+        if settings.confirm_reset {
+            ui.label(t!("settings.reset_confirm"));
+
+            ui.horizontal(|ui| {
+                if ui.button(t!("settings.reset")).clicked() {
+                    app.message_tx.send(Message::ResetConfig).unwrap();
+                }
+
+                if ui.button(t!("common.cancel")).clicked() {
+                    edited.confirm_reset = false;
+                }
+            });
+        }
+    });
+
+    if edited.config != settings.config || edited.confirm_reset != settings.confirm_reset {
+        app.message_tx
+            .send(Message::GoTo(State::Settings(Box::new(edited))))
+            .unwrap();
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{
+    app::App,
+    export,
+    i18n::{t, tn},
+    stats::{BannerStats, Stats},
+    ui,
+};
+
+const CHART_HEIGHT: f32 = 80.0;
+const BAR_WIDTH: f32 = 8.0;
+
+pub fn show(ui: &mut egui::Ui, stats: &Stats, app: &App) {
+    ui.horizontal(|ui| {
+        ui.label(t!("common.uid", uid = stats.uid));
+
+        if ui::focus::first(ui.button(t!("common.save_csv"))).clicked() {
+            ui::export::save(
+                app,
+                &export::file_name(stats.game, "pull_stats", "csv"),
+                "CSV",
+                "csv",
+                &export::stats_csv(stats),
+            );
+        }
+    });
+
+    ui::export::directory(ui, app);
+
+    ui.separator();
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for banner in stats.banners.iter().filter(|b| b.total > 0) {
+            egui::CollapsingHeader::new(tn!("stats.pulls", banner.total, banner = banner.name))
+                .default_open(true)
+                .show(ui, |ui| show_banner(ui, banner));
+        }
+    });
+}
+
+fn show_banner(ui: &mut egui::Ui, banner: &BannerStats) {
+    egui::Grid::new(&banner.name).show(ui, |ui| {
+        ui.label(t!("stats.pity"));
+        ui.label(t!(
+            "stats.pity_value",
+            pity = banner.pity,
+            four_star = banner.four_star_pity
+        ));
+        ui.end_row();
+
+        ui.label(t!("stats.five_stars"));
+        ui.label(banner.five_stars.len().to_string());
+        ui.end_row();
+
+        ui.label(t!("stats.four_stars"));
+        ui.label(banner.four_stars.to_string());
+        ui.end_row();
+
+        ui.label(t!("stats.average_pity"));
+        ui.label(
+            banner
+                .average_pity()
+                .map(|a| format!("{a:.1}"))
+                .unwrap_or("-".to_string()),
+        );
+        ui.end_row();
+
+        if let Some((won, total)) = banner.fifty_fifty() {
+            ui.label(t!("stats.fifty_fifty"));
+            ui.label(format!(
+                "{won}/{total} ({:.0}%)",
+                won as f32 / total as f32 * 100.0
+            ));
+            ui.end_row();
+        }
+
+        if banner.unknown_fifty_fifty > 0 {
+            ui.label(t!("stats.fifty_fifty_unknown"))
+                .on_hover_text(t!("stats.fifty_fifty_unknown_hint"));
+            ui.label(banner.unknown_fifty_fifty.to_string());
+            ui.end_row();
+        }
+    });
+
+    if !banner.five_stars.is_empty() {
+        chart(ui, banner);
+    }
+}
+
+/// The pity of every five star over time, green for won and red for lost 50/50s
+fn chart(ui: &mut egui::Ui, banner: &BannerStats) {
+    let max_pity = banner
+        .five_stars
+        .iter()
+        .map(|f| f.pity)
+        .max()
+        .unwrap_or(1)
+        .max(1) as f32;
+
+    egui::ScrollArea::horizontal()
+        .id_salt(&banner.name)
+        .show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.spacing_mut().item_spacing.x = 2.0;
+
+                for five_star in &banner.five_stars {
+                    let (rect, response) = ui.allocate_exact_size(
+                        egui::vec2(BAR_WIDTH, CHART_HEIGHT),
+                        egui::Sense::hover(),
+                    );
+
+                    let color = match five_star.won {
+                        Some(true) => egui::Color32::from_rgb(80, 180, 90),
+                        Some(false) => egui::Color32::from_rgb(210, 80, 70),
+                        None => ui.visuals().selection.bg_fill,
+                    };
+
+                    let height = CHART_HEIGHT * five_star.pity as f32 / max_pity;
+                    let bar = egui::Rect::from_min_max(
+                        egui::pos2(rect.left(), rect.bottom() - height),
+                        rect.max,
+                    );
+
+                    ui.painter().rect_filled(bar, 1.0, color);
+
+                    response.on_hover_text(t!(
+                        "stats.bar",
+                        name = five_star.name,
+                        time = five_star.time,
+                        pity = five_star.pity
+                    ));
+                }
+            });
+        });
+}
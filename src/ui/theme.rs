@@ -0,0 +1,78 @@
+use crate::{
+    app::{App, Message, State, ThemeEditor},
+    i18n::t,
+    themes,
+};
+
+pub fn show(ui: &mut egui::Ui, editor: &ThemeEditor, app: &App) {
+    let mut edited = editor.clone();
+
+    ui.horizontal(|ui| {
+        ui.label(t!("theme.name"));
+        ui.text_edit_singleline(&mut edited.name);
+    });
+
+    egui::Grid::new("theme_colors").show(ui, |ui| {
+        let colors = &mut edited.colors;
+
+        for (label, color) in [
+            (t!("theme.bg"), &mut colors.bg),
+            (t!("theme.surface"), &mut colors.surface),
+            (t!("theme.border"), &mut colors.border),
+            (t!("theme.text"), &mut colors.text),
+            (t!("theme.accent"), &mut colors.accent),
+        ] {
+            ui.label(label);
+            ui.color_edit_button_srgba(color);
+            ui.end_row();
+        }
+    });
+
+    ui.separator();
+    preview(ui);
+    ui.separator();
+
+    ui.horizontal(|ui| {
+        if ui.button(t!("common.save")).clicked() {
+            app.message_tx
+                .send(Message::SaveTheme(edited.name.clone(), edited.colors))
+                .unwrap();
+        }
+
+        if ui.button(t!("common.cancel")).clicked() {
+            app.message_tx.send(Message::GoTo(State::Menu)).unwrap();
+        }
+
+        if ui.button(t!("logs.open_folder")).clicked()
+            && let Err(e) = themes::dir().and_then(|dir| Ok(open::that(dir)?))
+        {
+            app.message_tx
+                .send(Message::Toast(egui_notify::Toast::error(e.to_string())))
+                .unwrap();
+        }
+    });
+
+    if edited != *editor {
+        app.message_tx
+            .send(Message::GoTo(State::ThemeEditor(edited)))
+            .unwrap();
+    }
+}
+
+/// A few of the widgets the app uses, so every color shows up somewhere
+fn preview(ui: &mut egui::Ui) {
+    ui.label(t!("theme.preview"));
+
+    egui::Frame::group(ui.style()).show(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.add(egui::Button::new(t!("theme.preview_button")));
+            ui.add(egui::Button::new(t!("theme.preview_selected")).selected(true));
+            ui.hyperlink_to(t!("theme.preview_link"), "https://stardb.gg");
+        });
+
+        ui.horizontal(|ui| {
+            ui.add(egui::Checkbox::new(&mut true, t!("theme.preview_checkbox")));
+            ui.weak(t!("theme.preview_weak"));
+        });
+    });
+}
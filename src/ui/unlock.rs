@@ -0,0 +1,44 @@
+use crate::{
+    app::{App, Message, State},
+    credentials,
+    i18n::t,
+};
+
+pub fn show(ui: &mut egui::Ui, passphrase: &str, app: &App) {
+    let Some(locked) = &app.locked else {
+        return;
+    };
+
+    if locked.protection == credentials::Protection::Machine {
+        ui.label(t!("unlock.machine"));
+    } else {
+        let mut passphrase = passphrase.to_string();
+
+        ui.label(t!("common.passphrase"));
+        let passphrase_edit = ui.add(egui::TextEdit::singleline(&mut passphrase).password(true));
+
+        let enter = passphrase_edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+        if ui.button(t!("unlock.unlock")).clicked() || enter {
+            app.message_tx.send(Message::Unlock(passphrase)).unwrap();
+        } else if passphrase_edit.changed() {
+            app.message_tx
+                .send(Message::GoTo(State::Unlock(passphrase)))
+                .unwrap();
+        }
+    }
+
+    ui.horizontal(|ui| {
+        if ui.button(t!("unlock.skip")).clicked() {
+            app.message_tx.send(Message::SkipUnlock).unwrap();
+        }
+
+        if ui
+            .button(t!("unlock.forget"))
+            .on_hover_text(t!("unlock.forget_hint"))
+            .clicked()
+        {
+            app.message_tx.send(Message::ForgetCredentials).unwrap();
+        }
+    });
+}
@@ -0,0 +1,151 @@
+use crate::{
+    app::{App, Message, State},
+    i18n::t,
+    jobs, ui,
+    updater::{self, Channel, Notes, Release, Settings},
+};
+
+pub fn available(ui: &mut egui::Ui, release: &Release, app: &App) {
+    ui.label(t!(
+        "update.available",
+        version = release.version,
+        current = env!("CARGO_PKG_VERSION")
+    ));
+
+    release_notes(ui, &release.notes);
+
+    ui.horizontal(|ui| {
+        if ui::focus::first(ui.button(t!("update.update_now"))).clicked() {
+            let agent = app.agent.clone();
+            let release = release.clone();
+
+            let id = jobs::spawn(
+                &app.message_tx,
+                t!("update.job", version = release.version),
+                move |context| {
+                    updater::install(&agent, &release, context)?;
+                    Ok(release.version)
+                },
+                |result| match result {
+                    Ok(version) => Message::Installed(version),
+                    Err(e) => Message::GoTo(State::Error(t!("update.failed", error = e))),
+                },
+            );
+
+            app.message_tx
+                .send(Message::Wait(t!("update.updating"), id))
+                .unwrap();
+        }
+
+        if ui.button(t!("update.remind_later")).clicked() {
+            app.message_tx.send(Message::RemindLater).unwrap();
+        }
+
+        if ui.button(t!("update.turn_off")).clicked() {
+            let mut updates = app.config.updates.clone();
+            updates.enabled = false;
+
+            app.message_tx
+                .send(Message::UpdateSettings(updates))
+                .unwrap();
+            app.message_tx.send(Message::GoTo(State::Menu)).unwrap();
+        }
+    });
+}
+
+pub fn notes(ui: &mut egui::Ui, notes: &Notes, app: &App) {
+    ui.label(t!("update.updated", version = notes.version));
+
+    release_notes(ui, &notes.notes);
+
+    ui.horizontal(|ui| {
+        if ui::focus::first(ui.button(t!("update.continue"))).clicked() {
+            app.message_tx.send(Message::GoTo(State::Menu)).unwrap();
+        }
+
+        rollback(ui, app);
+    });
+}
+
+pub fn settings(ui: &mut egui::Ui, updates: &Settings, app: &App) {
+    let mut edited = updates.clone();
+
+    ui::focus::first(ui.checkbox(&mut edited.enabled, t!("update.check_on_startup")));
+
+    ui.horizontal(|ui| {
+        ui.radio_value(&mut edited.channel, Channel::Stable, t!("update.stable"));
+        ui.radio_value(&mut edited.channel, Channel::Beta, t!("update.beta"))
+            .on_hover_text(t!("update.beta_hint"));
+    });
+
+    ui.label(t!("update.source"));
+    ui.horizontal(|ui| {
+        ui.text_edit_singleline(&mut edited.source);
+
+        if ui.button(t!("common.reset")).clicked() {
+            edited.source = updater::DEFAULT_SOURCE.to_string();
+        }
+    });
+
+    let save = ui.add_enabled(
+        edited != app.config.updates,
+        egui::Button::new(t!("common.save")),
+    );
+
+    if save.clicked() {
+        app.message_tx
+            .send(Message::UpdateSettings(edited.clone()))
+            .unwrap();
+    }
+
+    ui.separator();
+
+    ui.label(t!("update.version", version = env!("CARGO_PKG_VERSION")));
+
+    ui.horizontal(|ui| {
+        if ui.button(t!("update.check_now")).clicked() {
+            app.check_updates(true);
+        }
+
+        rollback(ui, app);
+    });
+
+    if edited != *updates {
+        app.message_tx
+            .send(Message::GoTo(State::Updates(edited)))
+            .unwrap();
+    }
+}
+
+fn release_notes(ui: &mut egui::Ui, notes: &str) {
+    egui::ScrollArea::vertical()
+        .max_height(300.0)
+        .show(ui, |ui| {
+            if notes.is_empty() {
+                ui.label(t!("update.no_notes"));
+            } else {
+                ui.label(notes);
+            }
+        });
+}
+
+fn rollback(ui: &mut egui::Ui, app: &App) {
+    let button = ui
+        .add_enabled(
+            updater::has_previous(),
+            egui::Button::new(t!("update.rollback")),
+        )
+        .on_disabled_hover_text(t!("update.rollback_hint"));
+
+    if button.clicked() {
+        jobs::spawn(
+            &app.message_tx,
+            t!("update.rolling_back"),
+            |_| updater::rollback(),
+            |result| match result {
+                Ok(()) => Message::RolledBack,
+                Err(e) => Message::GoTo(State::Error(t!("update.rollback_failed", error = e))),
+            },
+        );
+    }
+}
@@ -0,0 +1,314 @@
+use std::{
+    fs,
+    io::{Read, Write},
+    path::PathBuf,
+    time::Duration,
+};
+
+use anyhow::Context as _;
+
+use crate::{i18n::t, jobs};
+
+pub const DEFAULT_SOURCE: &str = "https://api.github.com";
+const REPO_OWNER: &str = "juliuskreutz";
+const REPO_NAME: &str = "stardb-exporter";
+
+/// Replaces the request timeout of the settings, which is too short for the binary
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// ed25519 key the release zips are signed with, see `zipsign gen-key`
+const PUBLIC_KEY: &[u8] = include_bytes!("../keys/update.pub");
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Channel {
+    #[default]
+    Stable,
+    /// Also offers prereleases, i.e. versions like `2.11.0-beta.1`
+    Beta,
+}
+
+#[derive(Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Check for updates on startup
+    pub enabled: bool,
+    pub channel: Channel,
+    /// Github api url, can point to a local release server for testing
+    pub source: String,
+    /// Set by "Remind me later"
+    pub remind_after: Option<chrono::DateTime<chrono::Utc>>,
+    /// Version that was rolled back from, not offered again automatically
+    pub skipped: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            channel: Channel::Stable,
+            source: DEFAULT_SOURCE.to_string(),
+            remind_after: None,
+            skipped: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Whether to check on startup
+    pub fn check_on_startup(&self) -> bool {
+        self.enabled
+            && !cfg!(debug_assertions)
+            && self
+                .remind_after
+                .is_none_or(|remind_after| remind_after <= chrono::Utc::now())
+    }
+}
+
+#[derive(Clone)]
+pub struct Release {
+    pub version: String,
+    pub notes: String,
+    /// The zip for this platform
+    asset: Asset,
+}
+
+#[derive(Clone, serde::Deserialize)]
+struct Asset {
+    name: String,
+    /// Api url, answers with the file for `Accept: application/octet-stream`
+    url: String,
+}
+
+#[derive(serde::Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    body: Option<String>,
+    assets: Vec<Asset>,
+}
+
+/// Release notes of the version that was just installed, shown once after the restart
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct Notes {
+    pub version: String,
+    pub notes: String,
+}
+
+/// The newest release on the channel that is newer than the running version. Goes through
+/// `agent`, so the proxy settings apply
+pub fn check(
+    agent: &ureq::Agent,
+    settings: &Settings,
+    manual: bool,
+) -> anyhow::Result<Option<Release>> {
+    let url = format!(
+        "{}/repos/{REPO_OWNER}/{REPO_NAME}/releases",
+        settings.source.trim_end_matches('/')
+    );
+
+    let mut response = agent
+        .get(&url)
+        .header("Accept", "application/vnd.github+json")
+        .header("User-Agent", REPO_NAME)
+        .call()?;
+    anyhow::ensure!(
+        response.status().is_success(),
+        "The update source responded with status {}",
+        response.status()
+    );
+
+    let releases: Vec<GithubRelease> = response.body_mut().read_json()?;
+
+    let mut newest: Option<Release> = None;
+
+    for release in releases {
+        let Some(asset) = release
+            .assets
+            .into_iter()
+            .find(|a| a.name.contains(self_update::get_target()) && a.name.ends_with(".zip"))
+        else {
+            continue;
+        };
+
+        let release = Release {
+            version: release.tag_name.trim_start_matches('v').to_string(),
+            notes: release.body.unwrap_or_default(),
+            asset,
+        };
+
+        if settings.channel == Channel::Stable && release.version.contains('-') {
+            continue;
+        }
+
+        if !manual && settings.skipped.as_deref() == Some(release.version.as_str()) {
+            continue;
+        }
+
+        let current = newest
+            .as_ref()
+            .map(|r| r.version.as_str())
+            .unwrap_or(env!("CARGO_PKG_VERSION"));
+
+        if self_update::version::bump_is_greater(current, &release.version).unwrap_or_default() {
+            newest = Some(release);
+        }
+    }
+
+    Ok(newest)
+}
+
+/// Downloads and verifies `release`, keeps the running binary for a rollback and replaces it
+pub fn install(
+    agent: &ureq::Agent,
+    release: &Release,
+    context: &jobs::Context,
+) -> anyhow::Result<()> {
+    let key: [u8; 32] = PUBLIC_KEY
+        .try_into()
+        .context("This build has no update signing key, download the update manually")?;
+
+    context.progress(t!("update.backing_up"), None);
+    let previous = previous_path()?;
+    fs::copy(std::env::current_exe()?, &previous)
+        .context("Couldn't back up the current version")?;
+
+    if let Err(e) = replace(agent, release, key, context) {
+        // The backup is the running version, nothing to roll back to
+        let _ = fs::remove_file(previous);
+        return Err(e);
+    }
+
+    tracing::info!("Updated to {}", release.version);
+
+    let notes = Notes {
+        version: release.version.clone(),
+        notes: release.notes.clone(),
+    };
+    fs::write(notes_path()?, serde_json::to_vec(&notes)?)?;
+
+    Ok(())
+}
+
+fn replace(
+    agent: &ureq::Agent,
+    release: &Release,
+    key: [u8; 32],
+    context: &jobs::Context,
+) -> anyhow::Result<()> {
+    context.progress(t!("update.downloading", version = release.version), None);
+
+    let dir = self_update::TempDir::new()?;
+    let archive = dir.path().join(&release.asset.name);
+
+    let mut response = agent
+        .get(&release.asset.url)
+        .header("Accept", "application/octet-stream")
+        .header("User-Agent", REPO_NAME)
+        .config()
+        .timeout_global(Some(DOWNLOAD_TIMEOUT))
+        .build()
+        .call()?;
+    anyhow::ensure!(
+        response.status().is_success(),
+        "The update source responded with status {}",
+        response.status()
+    );
+
+    let length = response.body().content_length();
+    let mut reader = response.body_mut().as_reader();
+    let mut file = fs::File::create(&archive)?;
+    let mut buffer = vec![0; 64 * 1024];
+    let mut downloaded = 0;
+
+    loop {
+        context.check()?;
+
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+
+        file.write_all(&buffer[..read])?;
+        downloaded += read as u64;
+
+        context.progress(
+            t!("update.downloading", version = release.version),
+            length.map(|l| downloaded as f32 / l as f32),
+        );
+    }
+    file.flush()?;
+    drop(file);
+
+    // Zipsign uses the file name as context, so a signature can't be moved to another file
+    let keys = zipsign_api::verify::collect_keys([Ok(key)])?;
+    zipsign_api::verify::verify_zip(
+        &mut fs::File::open(&archive)?,
+        &keys,
+        Some(release.asset.name.as_bytes()),
+    )
+    .context("The update isn't signed with the release key")?;
+
+    let bin_name = format!("{REPO_NAME}{}", std::env::consts::EXE_SUFFIX);
+    self_update::Extract::from_source(&archive)
+        .archive(self_update::ArchiveKind::Zip)
+        .extract_file(dir.path(), &bin_name)?;
+
+    self_update::self_replace::self_replace(dir.path().join(bin_name))?;
+
+    Ok(())
+}
+
+/// Puts the binary from before the last update back in place
+pub fn rollback() -> anyhow::Result<()> {
+    let previous = previous_path()?;
+
+    anyhow::ensure!(previous.exists(), "There is no previous version");
+
+    self_update::self_replace::self_replace(&previous)?;
+    fs::remove_file(previous)?;
+
+    let _ = fs::remove_file(notes_path()?);
+
+    Ok(())
+}
+
+pub fn has_previous() -> bool {
+    previous_path().is_ok_and(|p| p.exists())
+}
+
+/// Notes of a freshly installed update. Removed once read
+pub fn take_notes() -> Option<Notes> {
+    let path = notes_path().ok()?;
+    let notes = fs::read(&path).ok()?;
+    let _ = fs::remove_file(path);
+
+    serde_json::from_slice(&notes).ok()
+}
+
+pub fn restart() {
+    match std::env::current_exe() {
+        Ok(exe) => {
+            if let Err(e) = std::process::Command::new(exe).spawn() {
+                tracing::error!("Couldn't restart: {e}");
+            }
+        }
+        Err(e) => tracing::error!("Couldn't restart: {e}"),
+    }
+}
+
+fn previous_path() -> anyhow::Result<PathBuf> {
+    let mut path = crate::storage_dir("updates")?;
+    path.push(if cfg!(windows) {
+        "previous.exe"
+    } else {
+        "previous"
+    });
+
+    Ok(path)
+}
+
+fn notes_path() -> anyhow::Result<PathBuf> {
+    let mut path = crate::storage_dir("updates")?;
+    path.push("notes.json");
+
+    Ok(path)
+}